@@ -0,0 +1,73 @@
+//! Helpers shared by the benchmarks.
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use rustafarian_drone::{LogLevel, RustafarianDrone, RustafarianDroneBuilder};
+use std::collections::HashMap;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, Packet, PacketType};
+
+/// Fragments sent through the drones in each iteration
+pub const FRAGMENTS: u64 = 1000;
+
+/// Create a full fragment of a message.
+///
+/// ### Arguments
+///
+/// * `fragment_index: u64` - The index of the fragment
+/// * `total_n_fragments: u64` - The number of fragments of the message
+/// * `hops: Vec<NodeId>` - The route of the fragment, starting from the sender
+pub fn create_fragment(fragment_index: u64, total_n_fragments: u64, hops: Vec<NodeId>) -> Packet {
+    Packet {
+        pack_type: PacketType::MsgFragment(Fragment {
+            fragment_index,
+            total_n_fragments,
+            length: 128,
+            data: [1; 128],
+        }),
+        routing_header: SourceRoutingHeader { hop_index: 1, hops },
+        session_id: 1,
+    }
+}
+
+/// Create drone 11, with its packet channel already filled with fragments to forward from
+/// client 1 to drone 12, and its input channels closed: `run` returns once every fragment is
+/// forwarded, without any other thread involved.
+///
+/// ### Arguments
+///
+/// * `count: u64` - The number of fragments to forward
+/// * `route_length: usize` - The number of hops of the fragments, at least 3
+/// * `event_send: Sender<DroneEvent>` - Channel used by the drone to send its events
+/// ---
+/// ### Returns
+/// The drone, and the channel of drone 12, which must outlive the run
+pub fn loaded_drone(
+    count: u64,
+    route_length: usize,
+    event_send: Sender<DroneEvent>,
+) -> (RustafarianDrone, Receiver<Packet>) {
+    let (packet_send, packet_recv) = unbounded();
+    let (next_send, next_recv) = unbounded();
+
+    // The fragment is at the beginning of a long route, as in a large network
+    let mut hops = vec![1, 11];
+    hops.extend((12..).take(route_length - 2));
+
+    for fragment_index in 0..count {
+        packet_send
+            .send(create_fragment(fragment_index, count, hops.clone()))
+            .unwrap();
+    }
+
+    let drone = RustafarianDroneBuilder::new(
+        11,
+        event_send,
+        unbounded().1,
+        packet_recv,
+        HashMap::from([(12, next_send)]),
+    )
+    .log_level(LogLevel::NONE)
+    .build();
+
+    (drone, next_recv)
+}
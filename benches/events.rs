@@ -0,0 +1,65 @@
+//! Cost of the events sent to the Simulation Controller while forwarding fragments.
+//!
+//! Run with `cargo bench --bench events`.
+mod common;
+
+use common::{loaded_drone, FRAGMENTS};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use crossbeam_channel::unbounded;
+use std::thread;
+use wg_2024::drone::Drone;
+
+/// A single drone forwards the fragments on the current thread, while its events are:
+/// - `consumed` - Read by another thread, as the Simulation Controller would do
+/// - `queued` - Kept in the channel, nobody reads them during the run
+/// - `disconnected` - Lost, the receiver of the channel is dropped before the run
+fn event_channel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("event_channel");
+    group.throughput(Throughput::Elements(FRAGMENTS));
+
+    group.bench_function("consumed", |b| {
+        b.iter_batched(
+            || {
+                let (event_send, event_recv) = unbounded();
+                // The consumer stops once the drone, the only sender, is dropped
+                let consumer = thread::spawn(move || for _event in event_recv {});
+                (loaded_drone(FRAGMENTS, 5, event_send), consumer)
+            },
+            |((mut drone, next_recv), consumer)| {
+                drone.run();
+                (next_recv, consumer)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("queued", |b| {
+        b.iter_batched(
+            || {
+                let (event_send, event_recv) = unbounded();
+                (loaded_drone(FRAGMENTS, 5, event_send), event_recv)
+            },
+            |((mut drone, next_recv), event_recv)| {
+                drone.run();
+                (next_recv, event_recv)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("disconnected", |b| {
+        b.iter_batched(
+            || loaded_drone(FRAGMENTS, 5, unbounded().0),
+            |(mut drone, next_recv)| {
+                drone.run();
+                next_recv
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, event_channel);
+criterion_main!(benches);
@@ -0,0 +1,217 @@
+//! Time needed to complete a flood on different topologies.
+//!
+//! Run with `cargo bench --bench flooding`.
+use criterion::{criterion_group, criterion_main, Criterion};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use rustafarian_drone::{LogLevel, RustafarianDroneBuilder};
+use std::collections::HashMap;
+use std::thread;
+use wg_2024::controller::DroneCommand;
+use wg_2024::drone::Drone;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{FloodRequest, NodeType, Packet, PacketType};
+
+/// ID of the client starting the floods
+const CLIENT: NodeId = 1;
+
+/// A network of drones, with client 1 connected to the first drone of the topology.
+/// Dropping it crashes every drone and removes its neighbors, so that the drone threads end.
+struct Network {
+    first_send: Sender<Packet>,
+    client_recv: Receiver<Packet>,
+    responses: usize,
+    flood_id: u64,
+    // Command channel of every drone, with the IDs of its neighbors
+    drones: Vec<(Sender<DroneCommand>, Vec<NodeId>)>,
+}
+
+impl Network {
+    /// Start a drone for each entry of the topology, connected to the given drones.
+    ///
+    /// ### Arguments
+    ///
+    /// * `topology: &[(NodeId, Vec<NodeId>)]` - The drones with their neighbors, links are
+    ///   undirected and listed on both sides
+    fn new(topology: &[(NodeId, Vec<NodeId>)]) -> Self {
+        let (client_send, client_recv) = unbounded();
+        let mut channels: HashMap<NodeId, _> =
+            topology.iter().map(|(id, _)| (*id, unbounded())).collect();
+        let first_id = topology[0].0;
+
+        // The events are consumed, as the Simulation Controller would do
+        let (event_send, event_recv) = unbounded();
+        thread::spawn(move || for _event in event_recv {});
+
+        let mut drones = Vec::new();
+        for (id, neighbor_ids) in topology {
+            let mut neighbors: HashMap<NodeId, Sender<Packet>> = neighbor_ids
+                .iter()
+                .map(|neighbor_id| (*neighbor_id, channels[neighbor_id].0.clone()))
+                .collect();
+            if *id == first_id {
+                neighbors.insert(CLIENT, client_send.clone());
+            }
+
+            let ids = neighbors.keys().copied().collect();
+
+            let (command_send, command_recv) = unbounded();
+            let mut drone = RustafarianDroneBuilder::new(
+                *id,
+                event_send.clone(),
+                command_recv,
+                channels[id].1.clone(),
+                neighbors,
+            )
+            .log_level(LogLevel::NONE)
+            .build();
+            thread::spawn(move || drone.run());
+            drones.push((command_send, ids));
+        }
+
+        Self {
+            first_send: channels.remove(&first_id).unwrap().0,
+            client_recv,
+            responses: expected_responses(topology),
+            flood_id: 0,
+            drones,
+        }
+    }
+
+    /// Start a new flood from the client and wait until every flood response is received
+    fn flood(&mut self) {
+        self.flood_id += 1;
+        let packet = Packet {
+            pack_type: PacketType::FloodRequest(FloodRequest {
+                flood_id: self.flood_id,
+                initiator_id: CLIENT,
+                path_trace: vec![(CLIENT, NodeType::Client)],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 0,
+                hops: vec![],
+            },
+            session_id: self.flood_id,
+        };
+        self.first_send.send(packet).unwrap();
+
+        for _ in 0..self.responses {
+            self.client_recv.recv().unwrap();
+        }
+    }
+}
+
+impl Drop for Network {
+    fn drop(&mut self) {
+        for (command_send, neighbors) in &self.drones {
+            let _ = command_send.send(DroneCommand::Crash);
+            for neighbor in neighbors {
+                let _ = command_send.send(DroneCommand::RemoveSender(*neighbor));
+            }
+        }
+    }
+}
+
+/// Number of flood responses the client receives for each flood.
+///
+/// A drone receiving the request for the first time forwards it to every neighbor but the
+/// sender, or answers if there is none. Every other request received is a duplicate, and is
+/// answered.
+fn expected_responses(topology: &[(NodeId, Vec<NodeId>)]) -> usize {
+    // The first drone receives the request from the client, which is not in the topology
+    let forwarded: usize = topology[0].1.len()
+        + topology[1..]
+            .iter()
+            .map(|(_, neighbors)| neighbors.len() - 1)
+            .sum::<usize>();
+    let duplicates = forwarded - (topology.len() - 1);
+    let dead_ends = topology[1..]
+        .iter()
+        .filter(|(_, neighbors)| neighbors.len() == 1)
+        .count();
+    duplicates + dead_ends
+}
+
+/// Drones in a `side` x `side` grid, numbered from 11 row by row, each connected to the drones
+/// above, below, on the left and on the right
+fn mesh(side: u8) -> Vec<(NodeId, Vec<NodeId>)> {
+    let id = |row: u8, column: u8| 11 + row * side + column;
+    let mut topology = Vec::new();
+    for row in 0..side {
+        for column in 0..side {
+            let mut neighbors = Vec::new();
+            if row > 0 {
+                neighbors.push(id(row - 1, column));
+            }
+            if row + 1 < side {
+                neighbors.push(id(row + 1, column));
+            }
+            if column > 0 {
+                neighbors.push(id(row, column - 1));
+            }
+            if column + 1 < side {
+                neighbors.push(id(row, column + 1));
+            }
+            topology.push((id(row, column), neighbors));
+        }
+    }
+    topology
+}
+
+/// Drone 11 in the middle, connected to `leaves` drones numbered from 12, which are connected
+/// in a ring
+fn star(leaves: u8) -> Vec<(NodeId, Vec<NodeId>)> {
+    let leaf = |index: u8| 12 + index % leaves;
+    let mut topology = vec![(11, (0..leaves).map(leaf).collect())];
+    for index in 0..leaves {
+        topology.push((
+            leaf(index),
+            vec![11, leaf(index + leaves - 1), leaf(index + 1)],
+        ));
+    }
+    topology
+}
+
+/// Butterfly network with `rows` rows, a power of two, and `log2(rows) + 1` columns, numbered
+/// from 11 column by column. The drone in row `r` of column `c` is connected to the drones in
+/// rows `r` and `r ^ 2^c` of the next column.
+fn butterfly(rows: u8) -> Vec<(NodeId, Vec<NodeId>)> {
+    let columns = rows.trailing_zeros() as u8 + 1;
+    let id = |column: u8, row: u8| 11 + column * rows + row;
+    let mut topology = Vec::new();
+    for column in 0..columns {
+        for row in 0..rows {
+            let mut neighbors = Vec::new();
+            if column > 0 {
+                let bit = 1 << (column - 1);
+                neighbors.push(id(column - 1, row));
+                neighbors.push(id(column - 1, row ^ bit));
+            }
+            if column + 1 < columns {
+                let bit = 1 << column;
+                neighbors.push(id(column + 1, row));
+                neighbors.push(id(column + 1, row ^ bit));
+            }
+            topology.push((id(column, row), neighbors));
+        }
+    }
+    topology
+}
+
+fn flood_completion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flood_completion");
+
+    let topologies = [
+        ("mesh_4x4", mesh(4)),
+        ("star_10", star(10)),
+        ("butterfly_8", butterfly(8)),
+    ];
+    for (name, topology) in topologies {
+        let mut network = Network::new(&topology);
+        group.bench_function(name, |b| b.iter(|| network.flood()));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, flood_completion);
+criterion_main!(benches);
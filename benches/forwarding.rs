@@ -0,0 +1,132 @@
+//! Throughput of fragments forwarded by the drones.
+//!
+//! Run with `cargo bench --bench forwarding`.
+mod common;
+
+use common::{create_fragment, loaded_drone, FRAGMENTS};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use rustafarian_drone::{LogLevel, RustafarianDroneBuilder};
+use std::collections::HashMap;
+use std::thread;
+use wg_2024::controller::DroneCommand;
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// A line of drones, numbered from 11, between client 1 and server 2.
+/// Dropping it crashes every drone and removes its neighbors, so that the drone threads end.
+struct Chain {
+    first_send: Sender<Packet>,
+    server_recv: Receiver<Packet>,
+    hops: Vec<NodeId>,
+    // Command channel of every drone, with the IDs of its neighbors
+    drones: Vec<(Sender<DroneCommand>, [NodeId; 2])>,
+}
+
+impl Chain {
+    fn new(length: u8) -> Self {
+        let ids: Vec<NodeId> = (11..11 + length).collect();
+        let (client_send, _client_recv) = unbounded();
+        let (server_send, server_recv) = unbounded();
+        let channels: Vec<_> = ids.iter().map(|_| unbounded()).collect();
+
+        // The events are consumed, as the Simulation Controller would do
+        let (event_send, event_recv) = unbounded();
+        thread::spawn(move || for _event in event_recv {});
+
+        let mut drones = Vec::new();
+        for (index, id) in ids.iter().enumerate() {
+            let previous = match index {
+                0 => (1, client_send.clone()),
+                _ => (ids[index - 1], channels[index - 1].0.clone()),
+            };
+            let next = match ids.get(index + 1) {
+                Some(next_id) => (*next_id, channels[index + 1].0.clone()),
+                None => (2, server_send.clone()),
+            };
+
+            let (previous_id, next_id) = (previous.0, next.0);
+
+            let (command_send, command_recv) = unbounded();
+            let mut drone = RustafarianDroneBuilder::new(
+                *id,
+                event_send.clone(),
+                command_recv,
+                channels[index].1.clone(),
+                HashMap::from([previous, next]),
+            )
+            .log_level(LogLevel::NONE)
+            .build();
+            thread::spawn(move || drone.run());
+            drones.push((command_send, [previous_id, next_id]));
+        }
+
+        let mut hops = vec![1];
+        hops.extend(&ids);
+        hops.push(2);
+
+        Self {
+            first_send: channels[0].0.clone(),
+            server_recv,
+            hops,
+            drones,
+        }
+    }
+
+    /// Send the fragments from the client and wait until the server receives all of them
+    fn send_fragments(&self, count: u64) {
+        for fragment_index in 0..count {
+            self.first_send
+                .send(create_fragment(fragment_index, count, self.hops.clone()))
+                .unwrap();
+        }
+
+        for _ in 0..count {
+            self.server_recv.recv().unwrap();
+        }
+    }
+}
+
+impl Drop for Chain {
+    fn drop(&mut self) {
+        for (command_send, neighbors) in &self.drones {
+            let _ = command_send.send(DroneCommand::Crash);
+            for neighbor in neighbors {
+                let _ = command_send.send(DroneCommand::RemoveSender(*neighbor));
+            }
+        }
+    }
+}
+
+fn forward_fragments(c: &mut Criterion) {
+    let mut group = c.benchmark_group("forward_fragments");
+    group.throughput(Throughput::Elements(FRAGMENTS));
+
+    for length in [1, 5, 20] {
+        let chain = Chain::new(length);
+        group.bench_function(format!("chain_{}", length), |b| {
+            b.iter(|| chain.send_fragments(FRAGMENTS))
+        });
+    }
+
+    // Only the work of the drone, on the current thread
+    group.bench_function("single_drone", |b| {
+        b.iter_batched(
+            || {
+                let (event_send, event_recv) = unbounded();
+                (loaded_drone(FRAGMENTS, 20, event_send), event_recv)
+            },
+            |((mut drone, next_recv), event_recv)| {
+                drone.run();
+                (next_recv, event_recv)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, forward_fragments);
+criterion_main!(benches);
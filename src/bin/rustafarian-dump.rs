@@ -0,0 +1,53 @@
+use rustafarian_drone::capture::{read_capture, CaptureRecord, Direction};
+use rustafarian_drone::packet_type_name;
+use std::env;
+use std::process::exit;
+
+/// Print the records of a packet capture, including its rotated files, one line per packet.
+///
+/// Usage: `rustafarian-dump path/to/capture.jsonl`
+fn main() {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("Usage: rustafarian-dump <capture file>");
+        exit(2);
+    };
+
+    let records = match read_capture(&path) {
+        Ok(records) => records,
+        Err(error) => {
+            eprintln!("Couldn't read {}: {}", path, error);
+            exit(1);
+        }
+    };
+
+    let start = records.first().map_or(0, |record| record.timestamp_us);
+    for record in &records {
+        println!("{}", format_record(record, start));
+    }
+}
+
+/// Format a record as: time since the first record, drone, direction and neighbor, packet type,
+/// session ID and routing header.
+fn format_record(record: &CaptureRecord, start: u64) -> String {
+    let elapsed = record.timestamp_us.saturating_sub(start);
+    let neighbor = record
+        .neighbor
+        .map_or("?".to_string(), |neighbor| neighbor.to_string());
+    let direction = match record.direction {
+        Direction::Ingress => format!("<- {}", neighbor),
+        Direction::Egress => format!("-> {}", neighbor),
+    };
+    let header = &record.packet.routing_header;
+
+    format!(
+        "{:>8}.{:06} drone {} {:<6} {:<13} session {} hops {:?} @{}",
+        elapsed / 1_000_000,
+        elapsed % 1_000_000,
+        record.drone,
+        direction,
+        packet_type_name(&record.packet.pack_type),
+        record.packet.session_id,
+        header.hops,
+        header.hop_index
+    )
+}
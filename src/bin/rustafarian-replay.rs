@@ -0,0 +1,131 @@
+use rustafarian_drone::capture::read_capture;
+use rustafarian_drone::packet_type_name;
+use rustafarian_drone::replay::{Divergence, Replay};
+use std::env;
+use std::process::exit;
+use std::str::FromStr;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+const USAGE: &str = "Usage: rustafarian-replay <capture file> [--drone <id>] [--pdr <rate>] \
+                     [--seed <seed>] [--speed <factor>]";
+
+/// Replay a packet capture into a new drone and print the steps where the packets it sends differ
+/// from the recorded ones. The process exits with 1 when the replay diverges.
+///
+/// Usage: `rustafarian-replay path/to/capture.jsonl [options]`
+/// - `--drone <id>` - The drone to replay, by default the one of the first record
+/// - `--pdr <rate>` / `--seed <seed>` - The PDR and the seed of the recorded drone
+/// - `--speed <factor>` - Inject the packets with the recorded timing, sped up by the factor
+fn main() {
+    let mut args = env::args().skip(1);
+    let Some(path) = args.next() else {
+        usage();
+    };
+    let mut drone: Option<NodeId> = None;
+    let mut pdr: Option<f32> = None;
+    let mut seed: Option<u64> = None;
+    let mut speed: Option<f64> = None;
+    while let Some(option) = args.next() {
+        match option.as_str() {
+            "--drone" => drone = Some(value(args.next())),
+            "--pdr" => pdr = Some(value(args.next())),
+            "--seed" => seed = Some(value(args.next())),
+            "--speed" => speed = Some(value(args.next())),
+            _ => usage(),
+        }
+    }
+
+    let records = match read_capture(&path) {
+        Ok(records) => records,
+        Err(error) => {
+            eprintln!("Couldn't read {}: {}", path, error);
+            exit(1);
+        }
+    };
+    let Some(drone) = drone.or(records.first().map(|record| record.drone)) else {
+        eprintln!("{} is empty", path);
+        exit(1);
+    };
+
+    let mut replay = Replay::new(drone, records);
+    if let Some(speed) = speed {
+        if speed <= 0.0 {
+            usage();
+        }
+        replay = replay.original_timing(speed);
+    }
+    println!(
+        "Replaying {} packets into drone {}, with neighbors {:?}",
+        replay.len(),
+        drone,
+        replay.neighbors()
+    );
+
+    let report = replay.run_with(|mut builder| {
+        if let Some(pdr) = pdr {
+            builder = builder.pdr(pdr);
+        }
+        if let Some(seed) = seed {
+            builder = builder.seed(seed);
+        }
+        builder
+    });
+
+    for divergence in &report.divergences {
+        print_divergence(divergence);
+    }
+    println!(
+        "{} packets injected, {} steps matched, {} diverged",
+        report.injected,
+        report.matched,
+        report.divergences.len()
+    );
+    if !report.is_exact() {
+        exit(1);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    exit(2);
+}
+
+/// Parse the value of an option, exiting with the usage if it's missing or invalid
+fn value<T: FromStr>(arg: Option<String>) -> T {
+    arg.and_then(|arg| arg.parse().ok())
+        .unwrap_or_else(|| usage())
+}
+
+fn print_divergence(divergence: &Divergence) {
+    match &divergence.ingress {
+        Some(packet) => println!(
+            "Step {}: received {}",
+            divergence.step,
+            format_packet(packet)
+        ),
+        None => println!("Step {}: sent before receiving any packet", divergence.step),
+    }
+    for (label, packets) in [
+        ("recorded", &divergence.recorded),
+        ("replayed", &divergence.replayed),
+    ] {
+        if packets.is_empty() {
+            println!("    {}: nothing sent", label);
+        }
+        for (neighbor, packet) in packets {
+            println!("    {}: -> {} {}", label, neighbor, format_packet(packet));
+        }
+    }
+}
+
+/// Format a packet as: packet type, session ID and routing header
+fn format_packet(packet: &Packet) -> String {
+    format!(
+        "{} session {} hops {:?} @{}",
+        packet_type_name(&packet.pack_type),
+        packet.session_id,
+        packet.routing_header.hops,
+        packet.routing_header.hop_index
+    )
+}
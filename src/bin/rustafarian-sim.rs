@@ -0,0 +1,35 @@
+use rustafarian_drone::initializer::{load_config, start_network};
+use std::env;
+use std::process::exit;
+
+const DEFAULT_CONFIG: &str = "config/sample.toml";
+
+/// Boot a network of Rustafarian drones from a wg_2024 TOML configuration and print every
+/// event the drones send to the Simulation Controller.
+///
+/// Usage: `rustafarian-sim [path/to/config.toml]`
+fn main() {
+    let path = env::args().nth(1).unwrap_or(DEFAULT_CONFIG.to_string());
+
+    let config = match load_config(&path) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Couldn't load {}: {}", path, error);
+            exit(1);
+        }
+    };
+
+    let network = start_network(&config);
+    println!(
+        "Started {} drones, {} clients and {} servers from {}",
+        network.drone_commands.len(),
+        network.clients.len(),
+        network.servers.len(),
+        path
+    );
+
+    // The network keeps running until the process is stopped
+    while let Ok(event) = network.drone_events.recv() {
+        println!("{:?}", event);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::capture::CaptureWriter;
+use crate::congestion::{FullQueuePolicy, OverflowQueues};
+use crate::drone_core::DroneCore;
+use crate::drop_policy::{Bernoulli, DropPolicy};
+use crate::flood_cache::{FloodCache, FloodCacheConfig};
+use crate::logging::LogSink;
+#[cfg(not(feature = "tracing"))]
+use crate::logging::StdSink;
+#[cfg(feature = "tracing")]
+use crate::logging::TracingSink;
+use crate::priority::PriorityInbox;
+use crate::{
+    drone_seed, global_seed_from_env, saturate_pdr, LogLevel, RustafarianCommand, RustafarianDrone,
+};
+use crossbeam_channel::{never, Receiver, Sender};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::collections::HashMap;
+use std::mem;
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Builder used to create a [`RustafarianDrone`] with settings that are specific to a single
+/// instance, instead of relying on process-wide environment variables.
+///
+/// Every setting that is not explicitly provided falls back to the same default used by
+/// [`wg_2024::drone::Drone::new`].
+///
+/// ### Example
+///
+/// ```no_run
+/// # use crossbeam_channel::unbounded;
+/// # use std::collections::HashMap;
+/// use rustafarian_drone::{LogLevel, RustafarianDroneBuilder};
+///
+/// # let (controller_send, _) = unbounded();
+/// # let (_, controller_recv) = unbounded();
+/// # let (_, packet_recv) = unbounded();
+/// let drone = RustafarianDroneBuilder::new(1, controller_send, controller_recv, packet_recv, HashMap::new())
+///     .pdr(0.1)
+///     .log_level(LogLevel::DEBUG)
+///     .seed(42)
+///     .flood_cache_capacity(1024)
+///     .build();
+/// ```
+pub struct RustafarianDroneBuilder {
+    id: NodeId,
+    controller_send: Sender<DroneEvent>,
+    controller_recv: Receiver<DroneCommand>,
+    packet_recv: Receiver<Packet>,
+    packet_send: HashMap<NodeId, Sender<Packet>>,
+    pdr: Option<f32>,
+    drop_policy: Option<Box<dyn DropPolicy>>,
+    link_policies: HashMap<NodeId, Box<dyn DropPolicy>>,
+    extension_recv: Option<Receiver<RustafarianCommand>>,
+    log_level: Option<LogLevel>,
+    log_sink: Option<Box<dyn LogSink>>,
+    seed: Option<u64>,
+    global_seed: Option<u64>,
+    rng: Option<Box<dyn RngCore + Send>>,
+    flood_cache: FloodCacheConfig,
+    control_burst: Option<usize>,
+    full_queue_policy: FullQueuePolicy,
+    send_timeout: Option<Duration>,
+    capture: Option<CaptureWriter>,
+}
+
+impl RustafarianDroneBuilder {
+    /// Create a new builder with the channels required by every drone.
+    ///
+    /// ### Arguments
+    ///
+    /// * `id: NodeId` - The ID of the drone
+    /// * `controller_send: Sender<DroneEvent>` - Channel used to send events to the Simulation Controller
+    /// * `controller_recv: Receiver<DroneCommand>` - Channel used to receive commands from the Simulation Controller
+    /// * `packet_recv: Receiver<Packet>` - Channel used to receive packets from the other nodes
+    /// * `packet_send: HashMap<NodeId, Sender<Packet>>` - Channels of the neighbors of the drone
+    pub fn new(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+    ) -> Self {
+        Self {
+            id,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            packet_send,
+            pdr: None,
+            drop_policy: None,
+            link_policies: HashMap::new(),
+            extension_recv: None,
+            log_level: None,
+            log_sink: None,
+            seed: None,
+            global_seed: None,
+            rng: None,
+            flood_cache: FloodCacheConfig::default(),
+            control_burst: None,
+            full_queue_policy: FullQueuePolicy::default(),
+            send_timeout: None,
+            capture: None,
+        }
+    }
+
+    /// Set the Packet Drop Rate, used as the base rate of the drop policy.
+    /// The value is saturated to `0.0..1.0`, `NaN` is treated as `0.0`.
+    /// Default: `0.0`, or the rate the drop policy was created with
+    pub fn pdr(mut self, pdr: f32) -> Self {
+        self.pdr = Some(pdr);
+        self
+    }
+
+    /// Set the model used to decide which fragments are dropped. The policy keeps the rates it
+    /// was created with, a PDR set before the policy is discarded: only a PDR set afterwards
+    /// updates its base rate. This way the settings applied after the PDR of a configuration file
+    /// are not overwritten by it.
+    /// Default: [`Bernoulli`], dropping every fragment independently with probability `pdr`
+    pub fn drop_policy<P: DropPolicy + 'static>(mut self, drop_policy: P) -> Self {
+        self.drop_policy = Some(Box::new(drop_policy));
+        self.pdr = None;
+        self
+    }
+
+    /// Set the drop rate of the link towards a neighbor, overriding the PDR on that link.
+    /// The value is saturated to `0.0..1.0`, `NaN` is treated as `0.0`.
+    pub fn link_pdr(self, node_id: NodeId, pdr: f32) -> Self {
+        self.link_drop_policy(node_id, Bernoulli::new(saturate_pdr(pdr)))
+    }
+
+    /// Set the drop policy of the link towards a neighbor, overriding the drop policy on that link.
+    pub fn link_drop_policy<P: DropPolicy + 'static>(mut self, node_id: NodeId, policy: P) -> Self {
+        self.link_policies.insert(node_id, Box::new(policy));
+        self
+    }
+
+    /// Set the channel used to receive the commands that are not part of the `DroneCommand`
+    /// protocol, see [`RustafarianCommand`].
+    /// Default: no channel, the commands can't be received
+    pub fn extension_commands(mut self, extension_recv: Receiver<RustafarianCommand>) -> Self {
+        self.extension_recv = Some(extension_recv);
+        self
+    }
+
+    /// Set the log level of the drone.
+    /// Default: the value of the `RUSTAFARIAN_LOG_LEVEL` environment variable. With the `tracing`
+    /// feature and no sink set, `DEBUG`, leaving the filtering to the subscriber
+    pub fn log_level(mut self, log_level: LogLevel) -> Self {
+        self.log_level = Some(log_level);
+        self
+    }
+
+    /// Set where the logs of the drone are written, see the [`logging`](crate::logging) module.
+    /// Default: [`StdSink`](crate::logging::StdSink), or
+    /// [`TracingSink`](crate::logging::TracingSink) with the `tracing` feature
+    pub fn log_sink<S: LogSink + 'static>(mut self, log_sink: S) -> Self {
+        self.log_sink = Some(Box::new(log_sink));
+        self
+    }
+
+    /// Set the seed of the random number generator used to drop packets.
+    /// Default: derived from the global seed, see [`RustafarianDroneBuilder::global_seed`]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the seed shared by the whole network. The seed of the drone is derived from it and
+    /// from the ID of the drone using [`drone_seed`](crate::drone_seed). Ignored if a seed or an
+    /// RNG is set.
+    /// Default: the value of the `RUSTAFARIAN_SEED` environment variable, if missing a seed taken
+    /// from the entropy of the system
+    pub fn global_seed(mut self, global_seed: u64) -> Self {
+        self.global_seed = Some(global_seed);
+        self
+    }
+
+    /// Set the random number generator used to drop packets, taking precedence over any seed.
+    pub fn rng<R: RngCore + Send + 'static>(mut self, rng: R) -> Self {
+        self.rng = Some(Box::new(rng));
+        self
+    }
+
+    /// Set the maximum number of flood requests the drone remembers. When the limit is reached,
+    /// the oldest flood request is forgotten, whatever its initiator. A capacity of 0 is raised
+    /// to 1.
+    /// Default: no limit other than the window of each initiator
+    pub fn flood_cache_capacity(mut self, capacity: usize) -> Self {
+        self.flood_cache.capacity = Some(capacity);
+        self
+    }
+
+    /// Set the number of flood IDs the drone remembers for each initiator. When the limit is
+    /// reached, the oldest flood of that initiator is forgotten. A window of 0 is raised to 1.
+    /// Default: [`DEFAULT_FLOOD_WINDOW`](crate::flood_cache::DEFAULT_FLOOD_WINDOW)
+    pub fn flood_cache_window(mut self, window: usize) -> Self {
+        self.flood_cache.window = window;
+        self
+    }
+
+    /// Set the time after which a flood request is forgotten.
+    /// Default: flood requests don't expire
+    pub fn flood_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.flood_cache.ttl = Some(ttl);
+        self
+    }
+
+    /// Handle the control packets (ACKs, NACKs and flood packets) before the fragments. The
+    /// packets waiting in the channel are moved to a queue for each class, and after
+    /// `control_burst` control packets in a row a waiting fragment is handled, so fragments are
+    /// never starved. A value of 0 is treated as 1.
+    /// Default: disabled, the packets are handled in arrival order. A good starting value is
+    /// [`DEFAULT_CONTROL_BURST`](crate::priority::DEFAULT_CONTROL_BURST)
+    pub fn priority_scheduling(mut self, control_burst: usize) -> Self {
+        self.control_burst = Some(control_burst);
+        self
+    }
+
+    /// Set what the drone does with a packet when the channel of a neighbor is full. Only
+    /// matters for neighbors with a bounded channel.
+    /// Default: [`FullQueuePolicy::Block`], waiting until the neighbor has room
+    pub fn full_queue_policy(mut self, policy: FullQueuePolicy) -> Self {
+        self.full_queue_policy = policy;
+        self
+    }
+
+    /// Set how long the drone waits for room in a full channel before applying the
+    /// [`FullQueuePolicy`]. Ignored with [`FullQueuePolicy::Block`].
+    /// Default: the policy is applied immediately
+    pub fn send_timeout(mut self, timeout: Duration) -> Self {
+        self.send_timeout = Some(timeout);
+        self
+    }
+
+    /// Record every packet received and sent by the drone, see the [`capture`](crate::capture)
+    /// module. Not used by the [`DroneCore`].
+    /// Default: packets are not recorded
+    pub fn capture(mut self, writer: CaptureWriter) -> Self {
+        self.capture = Some(writer);
+        self
+    }
+
+    /// The ID of the drone that is being built
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Create the drone using the provided settings.
+    pub fn build(mut self) -> RustafarianDrone {
+        let core = self.core();
+        RustafarianDrone {
+            stats: core.stats(),
+            core,
+            controller_send: self.controller_send,
+            controller_recv: self.controller_recv,
+            extension_recv: self.extension_recv.unwrap_or_else(never),
+            packet_recv: self.packet_recv,
+            neighbors: self.packet_send,
+            inbox: self.control_burst.map(PriorityInbox::new),
+            full_queue_policy: self.full_queue_policy,
+            send_timeout: self.send_timeout,
+            overflow: OverflowQueues::new(),
+            capture: self.capture,
+        }
+    }
+
+    /// Create only the [`DroneCore`] of the drone, to be driven by a runtime other than
+    /// [`RustafarianDrone`]. The neighbors are the keys of `packet_send`, the channels and the
+    /// settings of the runtime (`priority_scheduling`, `full_queue_policy`, `send_timeout`,
+    /// `capture`) are ignored.
+    pub fn build_core(mut self) -> DroneCore {
+        self.core()
+    }
+
+    /// Create the core of the drone, taking the settings it needs out of the builder.
+    fn core(&mut self) -> DroneCore {
+        let mut drop_policy = self
+            .drop_policy
+            .take()
+            .unwrap_or_else(|| Box::new(Bernoulli::new(0.0)));
+        if let Some(pdr) = self.pdr {
+            drop_policy.set_rate(saturate_pdr(pdr));
+        }
+
+        let logging = match self.log_sink.take() {
+            Some(log_sink) => {
+                let log_level = self.log_level.unwrap_or_else(LogLevel::from_env);
+                (log_level, log_sink)
+            }
+            None => default_logging(self.log_level),
+        };
+
+        let rng = match self.rng.take() {
+            Some(rng) => rng,
+            None => {
+                let seed = self.seed.or_else(|| {
+                    self.global_seed
+                        .or_else(global_seed_from_env)
+                        .map(|global_seed| drone_seed(global_seed, self.id))
+                });
+                let rng = match seed {
+                    Some(seed) => StdRng::seed_from_u64(seed),
+                    None => StdRng::from_entropy(),
+                };
+                Box::new(rng) as Box<dyn RngCore + Send>
+            }
+        };
+
+        DroneCore::new(
+            self.id,
+            self.packet_send.keys().copied().collect(),
+            drop_policy,
+            mem::take(&mut self.link_policies),
+            rng,
+            FloodCache::new(self.flood_cache),
+            logging,
+        )
+    }
+}
+
+/// Log level and sink used when no sink is provided: the standard output, filtered with the level
+/// from the environment.
+#[cfg(not(feature = "tracing"))]
+fn default_logging(log_level: Option<LogLevel>) -> (LogLevel, Box<dyn LogSink>) {
+    let log_level = log_level.unwrap_or_else(LogLevel::from_env);
+    (log_level, Box::new(StdSink))
+}
+
+/// Log level and sink used when no sink is provided: `tracing`, leaving the filtering to the
+/// subscriber unless a level is set.
+#[cfg(feature = "tracing")]
+fn default_logging(log_level: Option<LogLevel>) -> (LogLevel, Box<dyn LogSink>) {
+    (log_level.unwrap_or(LogLevel::DEBUG), Box::new(TracingSink))
+}
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Lines, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+
+/// Whether a captured packet entered or left the drone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    /// Received by the drone
+    Ingress,
+    /// Sent by the drone to a neighbor
+    Egress,
+}
+
+/// A packet that crossed a drone, as stored in a capture file.
+///
+/// A capture file is in the JSON Lines format: every line is a record, serialized as a JSON
+/// object with the fields below. The packet uses the `serialize` feature of wg_2024, e.g.
+///
+/// ```text
+/// {"timestamp_us":1733000000000000,"drone":11,"direction":"Ingress","neighbor":1,"packet":{...}}
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureRecord {
+    /// When the packet crossed the drone, in microseconds since the UNIX epoch
+    pub timestamp_us: u64,
+    /// The ID of the drone that captured the packet
+    pub drone: NodeId,
+    /// Whether the packet was received or sent
+    pub direction: Direction,
+    /// The neighbor the packet came from or was sent to. For received packets it's taken from
+    /// the packet itself, `None` if the packet doesn't tell
+    pub neighbor: Option<NodeId>,
+    /// The packet, as it was received or sent
+    pub packet: Packet,
+}
+
+impl CaptureRecord {
+    /// Create the record of a packet crossing the drone now.
+    pub fn new(
+        drone: NodeId,
+        direction: Direction,
+        neighbor: Option<NodeId>,
+        packet: Packet,
+    ) -> Self {
+        let timestamp_us = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_micros() as u64);
+        Self {
+            timestamp_us,
+            drone,
+            direction,
+            neighbor,
+            packet,
+        }
+    }
+}
+
+/// The node a received packet comes from: the last node of the path trace for flood requests,
+/// the previous hop of the routing header for the other packets.
+pub fn previous_hop(packet: &Packet) -> Option<NodeId> {
+    match &packet.pack_type {
+        PacketType::FloodRequest(request) => request.path_trace.last().map(|(id, _)| *id),
+        _ => {
+            let header = &packet.routing_header;
+            let index = header.hop_index.checked_sub(1)?;
+            header.hops.get(index).copied()
+        }
+    }
+}
+
+/// Error returned when a capture can't be written or read
+#[derive(Debug)]
+pub enum CaptureError {
+    /// The capture file couldn't be written or read
+    Io(io::Error),
+    /// A line of the capture file is not a valid record. Contains: (line number, error)
+    Parse(usize, serde_json::Error),
+}
+
+impl Display for CaptureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureError::Io(error) => write!(f, "couldn't access the capture: {}", error),
+            CaptureError::Parse(line, error) => {
+                write!(f, "invalid record at line {}: {}", line, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<io::Error> for CaptureError {
+    fn from(error: io::Error) -> Self {
+        CaptureError::Io(error)
+    }
+}
+
+/// Append capture records to a file, see [`CaptureRecord`] for the format.
+///
+/// With rotation, once the file would grow over the size limit it's renamed to `<path>.1`, the
+/// previous `<path>.1` to `<path>.2` and so on, and a new file is started. Only the given number
+/// of rotated files is kept, the oldest is deleted.
+#[derive(Debug)]
+pub struct CaptureWriter {
+    path: PathBuf,
+    file: File,
+    size: u64,                      // Bytes already in the file
+    rotation: Option<(u64, usize)>, // Maximum size of a file, rotated files kept
+}
+
+impl CaptureWriter {
+    /// Open the file in append mode, creating it if it doesn't exist.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            rotation: None,
+        })
+    }
+
+    /// Open the file `capture-<id>.jsonl` inside `dir`, so that every drone has its own file.
+    pub fn in_dir<P: AsRef<Path>>(dir: P, drone_id: NodeId) -> io::Result<Self> {
+        Self::new(dir.as_ref().join(format!("capture-{}.jsonl", drone_id)))
+    }
+
+    /// Rotate the file when it would grow over `max_bytes`, keeping up to `max_files` rotated
+    /// files. A single record bigger than `max_bytes` still gets its own file. With `max_files`
+    /// set to 0, the old records are deleted.
+    pub fn rotate(mut self, max_bytes: u64, max_files: usize) -> Self {
+        self.rotation = Some((max_bytes, max_files));
+        self
+    }
+
+    /// The path of the file being written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record to the file, rotating it first if needed.
+    pub fn write(&mut self, record: &CaptureRecord) -> Result<(), CaptureError> {
+        let mut line = serde_json::to_vec(record).map_err(io::Error::from)?;
+        line.push(b'\n');
+
+        if let Some((max_bytes, max_files)) = self.rotation {
+            if self.size > 0 && self.size + line.len() as u64 > max_bytes {
+                self.rotate_files(max_files)?;
+            }
+        }
+
+        // A single write for the whole line, so that a crash can only cut the last record
+        self.file.write_all(&line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shift the rotated files by one, move the current file to `<path>.1` and start a new one.
+    fn rotate_files(&mut self, max_files: usize) -> io::Result<()> {
+        if max_files == 0 {
+            self.file = File::create(&self.path)?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        remove_if_exists(&rotated_path(&self.path, max_files))?;
+        for index in (1..max_files).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Read the records of a capture file, one per line, see [`CaptureRecord`] for the format.
+/// Empty lines are skipped.
+pub struct CaptureReader<R> {
+    lines: Lines<BufReader<R>>,
+    line: usize, // Number of the last line read, starting from 1
+}
+
+impl CaptureReader<File> {
+    /// Open a capture file. Only the file itself is read, see [`read_capture`] to include the
+    /// rotated files.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Read the records from any reader, e.g. the standard input
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: BufReader::new(reader).lines(),
+            line: 0,
+        }
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(CaptureError::Io(error))),
+            };
+            self.line += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            return Some(
+                serde_json::from_str(&line).map_err(|error| CaptureError::Parse(self.line, error)),
+            );
+        }
+    }
+}
+
+/// Read every record of a capture, oldest first: the rotated files, from `<path>.N` to
+/// `<path>.1`, then the file itself.
+pub fn read_capture<P: AsRef<Path>>(path: P) -> Result<Vec<CaptureRecord>, CaptureError> {
+    let path = path.as_ref();
+    let mut files: Vec<PathBuf> = (1..)
+        .map(|index| rotated_path(path, index))
+        .take_while(|rotated| rotated.exists())
+        .collect();
+    files.reverse();
+    files.push(path.to_path_buf());
+
+    let mut records = Vec::new();
+    for file in files {
+        for record in CaptureReader::open(file)? {
+            records.push(record?);
+        }
+    }
+    Ok(records)
+}
+
+/// Path of the `index`-th rotated file of a capture, `<path>.<index>`
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,15 @@
+use wg_2024::network::NodeId;
+
+/// Commands specific to the Rustafarian drone, not part of the `DroneCommand` protocol.
+///
+/// They are received on a separate channel, set with
+/// [`RustafarianDroneBuilder::extension_commands`](crate::RustafarianDroneBuilder::extension_commands).
+/// Commands from the Simulation Controller always have priority over these.
+#[derive(Debug, Clone)]
+pub enum RustafarianCommand {
+    /// Set the drop rate of the link towards a neighbor, overriding the drone PDR.
+    /// Contains: (neighbor, pdr)
+    SetLinkDropRate(NodeId, f32),
+    /// Remove the drop rate of the link towards a neighbor, so that the drone PDR is used again
+    ClearLinkDropRate(NodeId),
+}
@@ -0,0 +1,112 @@
+use crossbeam_channel::{never, tick, Receiver};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Time between two attempts to send the packets held in the overflow queues
+pub const OVERFLOW_RETRY: Duration = Duration::from_millis(1);
+
+/// What the drone does with a packet when the channel of a neighbor is full. Only matters for
+/// neighbors with a bounded channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FullQueuePolicy {
+    /// Wait until the neighbor has room for the packet. The drone can't handle anything else
+    /// while waiting
+    #[default]
+    Block,
+    /// Drop the packet: fragments are answered with a `Dropped` NACK, ACKs, NACKs and flood
+    /// responses are delivered through the Simulation Controller, flood requests are lost
+    Drop,
+    /// Hold the packet in a queue of the neighbor, sent as soon as the channel has room.
+    /// Up to `capacity` packets are held for each neighbor, the others are dropped as with `Drop`
+    Overflow { capacity: usize },
+}
+
+/// Packets held for the neighbors with a full channel, see [`FullQueuePolicy::Overflow`].
+#[derive(Debug)]
+pub(crate) struct OverflowQueues {
+    queues: HashMap<NodeId, VecDeque<Packet>>, // Only neighbors with packets waiting
+    retry: Receiver<Instant>,                  // Ticks while packets are waiting
+}
+
+impl OverflowQueues {
+    pub(crate) fn new() -> Self {
+        Self {
+            queues: HashMap::new(),
+            retry: tick(OVERFLOW_RETRY),
+        }
+    }
+
+    /// Hold a packet for a neighbor, behind the ones already waiting.
+    ///
+    /// ### Arguments
+    ///
+    /// * `neighbor: NodeId` - The neighbor the packet is sent to
+    /// * `packet: Packet` - The packet to hold
+    /// * `capacity: usize` - Maximum number of packets held for the neighbor
+    /// ---
+    /// ### Returns
+    /// The packet if the queue of the neighbor is full
+    pub(crate) fn push(
+        &mut self,
+        neighbor: NodeId,
+        packet: Packet,
+        capacity: usize,
+    ) -> Option<Packet> {
+        let queue = self.queues.entry(neighbor).or_default();
+        if queue.len() >= capacity {
+            if queue.is_empty() {
+                self.queues.remove(&neighbor);
+            }
+            return Some(packet);
+        }
+        queue.push_back(packet);
+        None
+    }
+
+    /// Take the oldest packet held for a neighbor
+    pub(crate) fn pop(&mut self, neighbor: NodeId) -> Option<Packet> {
+        let queue = self.queues.get_mut(&neighbor)?;
+        let packet = queue.pop_front();
+        if queue.is_empty() {
+            self.queues.remove(&neighbor);
+        }
+        packet
+    }
+
+    /// Put back a packet that still can't be sent, in front of the others
+    pub(crate) fn push_front(&mut self, neighbor: NodeId, packet: Packet) {
+        self.queues.entry(neighbor).or_default().push_front(packet);
+    }
+
+    /// Forget a neighbor, returning the packets held for it
+    pub(crate) fn remove(&mut self, neighbor: NodeId) -> VecDeque<Packet> {
+        self.queues.remove(&neighbor).unwrap_or_default()
+    }
+
+    /// Whether packets are held for a neighbor
+    pub(crate) fn is_waiting(&self, neighbor: NodeId) -> bool {
+        self.queues.contains_key(&neighbor)
+    }
+
+    /// Neighbors with packets held
+    pub(crate) fn neighbors(&self) -> Vec<NodeId> {
+        self.queues.keys().copied().collect()
+    }
+
+    /// Number of packets held for a neighbor
+    pub(crate) fn len(&self, neighbor: NodeId) -> usize {
+        self.queues.get(&neighbor).map_or(0, VecDeque::len)
+    }
+
+    /// Channel that ticks while packets are held, used to retry sending them together with the
+    /// channels of the drone
+    pub(crate) fn retry(&self) -> Receiver<Instant> {
+        if self.queues.is_empty() {
+            never()
+        } else {
+            self.retry.clone()
+        }
+    }
+}
@@ -0,0 +1,912 @@
+use crate::drop_policy::{Bernoulli, DropPolicy};
+use crate::flood_cache::FloodCache;
+use crate::logging::{LogRecord, LogSink};
+use crate::stats::StatsHandle;
+use crate::validation::{validate_packet, PacketError};
+use crate::{packet_type_name, saturate_pdr, LogLevel, RustafarianCommand};
+use rand::RngCore;
+use std::collections::{BTreeSet, HashMap};
+use std::mem;
+use std::time::Instant;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{FloodRequest, FloodResponse, NackType, NodeType, Packet, PacketType};
+
+/// Something the drone has to do after handling an input, carried out by the runtime around the
+/// [`DroneCore`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Send a packet to a neighbor. The runtime reports the outcome with [`DroneCore::on_sent`]
+    /// or [`DroneCore::on_send_failed`]
+    Send { to: NodeId, packet: Packet },
+    /// Send an event to the Simulation Controller
+    Event(DroneEvent),
+    /// Deliver a packet through the Simulation Controller, with a `ControllerShortcut` event
+    Shortcut(Packet),
+}
+
+/// Why the runtime couldn't send a packet to a neighbor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendFailure {
+    /// The neighbor can't be reached: its channel is closed or it has been removed
+    Disconnected,
+    /// The channel of the neighbor is full, and the runtime gave up on the packet
+    Full,
+}
+
+/// A flood request forwarded to the neighbors, waiting for the outcome of every branch to know
+/// whether the drone is a dead end.
+#[derive(Debug)]
+struct FloodFanout {
+    request: FloodRequest, // The request, with the drone already added to the path trace
+    session_id: u64,
+    pending: usize, // Branches whose outcome hasn't been reported yet
+    reached: bool,  // Whether at least one branch left the drone
+}
+
+/// The logic of the drone, without any channel: every input is handled by an `on_*` method,
+/// which returns the [`Action`]s the runtime has to carry out.
+///
+/// [`RustafarianDrone`](crate::RustafarianDrone) is the runtime built on crossbeam channels, but
+/// the core can be driven by anything that delivers packets and reports whether the packets sent
+/// reached the neighbors, e.g. a test or a simulator.
+///
+/// ### Example
+///
+/// ```
+/// # use crossbeam_channel::unbounded;
+/// # use std::collections::HashMap;
+/// # use wg_2024::network::SourceRoutingHeader;
+/// # use wg_2024::packet::{Fragment, Packet, PacketType};
+/// use rustafarian_drone::drone_core::Action;
+/// use rustafarian_drone::RustafarianDroneBuilder;
+///
+/// # let packet = Packet {
+/// #     pack_type: PacketType::MsgFragment(Fragment {
+/// #         fragment_index: 0,
+/// #         total_n_fragments: 1,
+/// #         length: 128,
+/// #         data: [0; 128],
+/// #     }),
+/// #     routing_header: SourceRoutingHeader { hop_index: 1, hops: vec![1, 11, 12] },
+/// #     session_id: 1,
+/// # };
+/// let neighbors = HashMap::from([(1, unbounded().0), (12, unbounded().0)]);
+/// let mut core = RustafarianDroneBuilder::new(11, unbounded().0, unbounded().1, unbounded().1, neighbors)
+///     .build_core();
+/// for action in core.on_packet(packet) {
+///     if let Action::Send { to, packet } = action {
+///         // Deliver the packet to `to`, then report the outcome
+///         core.on_sent(to, packet);
+///     }
+/// }
+/// ```
+pub struct DroneCore {
+    id: NodeId,                       // The ID of the drone
+    drop_policy: Box<dyn DropPolicy>, // Decides which fragments are dropped
+    rng: Box<dyn RngCore + Send>,     // RNG used for the Packet Drop Rate
+    flood_cache: FloodCache,          // Flood requests already handled, bounded
+    crashed: bool,                    // Whether the drone is crashed
+    log_level: LogLevel,              // Level used to filter the logs
+    log_sink: Box<dyn LogSink>,       // Where the logs are written
+    stats: StatsHandle,               // Counters of the traffic handled by the drone
+    actions: Vec<Action>,             // Actions produced by the input being handled
+    clock: Option<Instant>,           // Time set by a virtual clock, `None` = system clock
+    // IDs of the neighbors, sorted so that flood requests are forwarded in a deterministic order.
+    // The channels belong to the runtime
+    neighbors: BTreeSet<NodeId>,
+    // Drop policies overriding the default one on a link. The key is the ID of the neighbor
+    link_policies: HashMap<NodeId, Box<dyn DropPolicy>>,
+    // Flood requests forwarded whose branches are still in flight, by initiator and flood ID
+    floods: HashMap<(NodeId, u64), FloodFanout>,
+}
+
+impl DroneCore {
+    /// Create the core of a drone, used by the builder.
+    pub(crate) fn new(
+        id: NodeId,
+        neighbors: BTreeSet<NodeId>,
+        drop_policy: Box<dyn DropPolicy>,
+        link_policies: HashMap<NodeId, Box<dyn DropPolicy>>,
+        rng: Box<dyn RngCore + Send>,
+        flood_cache: FloodCache,
+        (log_level, log_sink): (LogLevel, Box<dyn LogSink>),
+    ) -> Self {
+        Self {
+            id,
+            neighbors,
+            drop_policy,
+            rng,
+            flood_cache,
+            crashed: false,
+            log_level,
+            log_sink,
+            stats: StatsHandle::default(),
+            actions: Vec::new(),
+            clock: None,
+            link_policies,
+            floods: HashMap::new(),
+        }
+    }
+
+    /// Handle a packet received from another node.
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: Packet` - The packet, as it was received
+    /// ---
+    /// ### Returns
+    /// The actions the runtime has to carry out, in order
+    pub fn on_packet(&mut self, packet: Packet) -> Vec<Action> {
+        self.handle_packet(packet);
+        self.take_actions()
+    }
+
+    /// Handle a command from the Simulation Controller.
+    /// Once the drone is crashed, only `RemoveSender` is handled and the other commands are ignored.
+    ///
+    /// The channel of `AddSender` is not kept: the core only knows the IDs of the neighbors, the
+    /// channels belong to the runtime.
+    ///
+    /// ### Arguments
+    ///
+    /// * `command: DroneCommand` - The command to handle
+    /// ---
+    /// ### Returns
+    /// The actions the runtime has to carry out, in order
+    pub fn on_command(&mut self, command: DroneCommand) -> Vec<Action> {
+        if self.crashed && !matches!(command, DroneCommand::RemoveSender(_)) {
+            self.log(
+                format!("Ignored command {:?}, the drone is crashing", command).as_str(),
+                LogLevel::INFO,
+            );
+            return self.take_actions();
+        }
+
+        match command {
+            DroneCommand::AddSender(node_id, _) => {
+                self.neighbors.insert(node_id);
+            }
+            DroneCommand::SetPacketDropRate(pdr) => self.drop_policy.set_rate(saturate_pdr(pdr)),
+            DroneCommand::RemoveSender(node_id) => {
+                self.neighbors.remove(&node_id);
+            }
+            DroneCommand::Crash => self.crashed = true,
+        }
+        self.take_actions()
+    }
+
+    /// Handle a command that is not part of the `DroneCommand` protocol.
+    ///
+    /// ### Arguments
+    ///
+    /// * `command: RustafarianCommand` - The command to handle
+    /// ---
+    /// ### Returns
+    /// The actions the runtime has to carry out, in order
+    pub fn on_extension_command(&mut self, command: RustafarianCommand) -> Vec<Action> {
+        match command {
+            RustafarianCommand::SetLinkDropRate(node_id, pdr) => self.set_link_pdr(node_id, pdr),
+            RustafarianCommand::ClearLinkDropRate(node_id) => self.clear_link_pdr(node_id),
+        }
+        self.take_actions()
+    }
+
+    /// Record that a packet of an [`Action::Send`] reached the channel of the neighbor.
+    ///
+    /// ### Arguments
+    ///
+    /// * `to: NodeId` - The neighbor the packet was sent to
+    /// * `packet: Packet` - The packet sent
+    /// ---
+    /// ### Returns
+    /// The actions the runtime has to carry out: the `PacketSent` event
+    pub fn on_sent(&mut self, to: NodeId, packet: Packet) -> Vec<Action> {
+        let packet_type = packet_type_name(&packet.pack_type);
+        self.stats
+            .update(|stats| stats.record_sent(packet_type, to));
+        if let PacketType::FloodRequest(ref request) = packet.pack_type {
+            self.end_flood_branch(request.initiator_id, request.flood_id, true);
+        }
+
+        // Notify controller that a packet has been correctly sent
+        self.actions
+            .push(Action::Event(DroneEvent::PacketSent(packet)));
+        self.take_actions()
+    }
+
+    /// Handle a packet of an [`Action::Send`] that couldn't reach the neighbor:
+    /// - When the neighbor is disconnected, ACKs, NACKs and Flood responses are delivered through
+    ///   the Simulation Controller, fragments are answered with an `ErrorInRouting` NACK so that
+    ///   the sender finds another route, and flood requests are lost
+    /// - When the channel is full, the packet is dropped: fragments are answered with a `Dropped`
+    ///   NACK and reported with a `PacketDropped` event, flood requests are lost, the other
+    ///   packets are delivered through the Simulation Controller
+    ///
+    /// If no branch of a flood request leaves the drone, the drone is a dead end and answers
+    /// with a flood response.
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: Packet` - The packet, as it was in the action
+    /// * `to: NodeId` - The neighbor that couldn't be reached
+    /// * `failure: SendFailure` - Why the packet couldn't be sent
+    /// ---
+    /// ### Returns
+    /// The actions the runtime has to carry out, in order
+    pub fn on_send_failed(
+        &mut self,
+        packet: Packet,
+        to: NodeId,
+        failure: SendFailure,
+    ) -> Vec<Action> {
+        match failure {
+            SendFailure::Disconnected => {
+                // Should never happen, SC should prevent it
+                self.log_packet(
+                    "Error while sending packet on closed channel",
+                    LogLevel::ERROR,
+                    &packet,
+                );
+                self.undeliverable(packet, to);
+            }
+            SendFailure::Full => self.congestion_drop(packet, to),
+        }
+        self.take_actions()
+    }
+
+    /// The ID of the drone
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Whether the drone has received the `Crash` command
+    pub fn is_crashed(&self) -> bool {
+        self.crashed
+    }
+
+    /// Shared handle to the statistics of the drone, which can be read from another thread.
+    pub fn stats(&self) -> StatsHandle {
+        self.stats.clone()
+    }
+
+    /// Drive the core with a virtual clock: from now on `now` is used as the current time, e.g. to
+    /// expire the flood requests remembered, instead of the system clock.
+    pub fn set_clock(&mut self, now: Instant) {
+        self.clock = Some(now);
+    }
+
+    /// The current time, from the virtual clock if set
+    fn now(&self) -> Instant {
+        self.clock.unwrap_or_else(Instant::now)
+    }
+
+    /// Actions produced so far, leaving the list empty for the next input
+    pub(crate) fn take_actions(&mut self) -> Vec<Action> {
+        mem::take(&mut self.actions)
+    }
+
+    /// Handle packets that arrive from other drones.
+    fn handle_packet(&mut self, packet: Packet) {
+        self.stats.update(|stats| stats.record_received(&packet));
+
+        if let Err(error) = validate_packet(&packet) {
+            self.reject_packet(packet, error);
+            return;
+        }
+
+        // Packets are moved, not cloned: only the fragment index is copied out of the fragment
+        match packet.pack_type {
+            PacketType::Nack(_) => self.forward_packet(packet, true, 0),
+            PacketType::Ack(_) => self.forward_packet(packet, true, 0),
+            PacketType::MsgFragment(ref fragment) => {
+                let fragment_index = fragment.fragment_index;
+                if self.crashed {
+                    self.send_nack_fragment(
+                        &packet,
+                        NackType::ErrorInRouting(self.id),
+                        fragment_index,
+                    );
+                    return;
+                }
+
+                self.forward_packet(packet, false, fragment_index)
+            }
+            PacketType::FloodRequest(flood_request) => {
+                self.handle_flood_req(flood_request, packet.session_id, packet.routing_header);
+            }
+            PacketType::FloodResponse(_) => {
+                // If the packet is a flood response, we just forward it
+                self.forward_packet(packet, true, 0);
+            }
+        }
+    }
+
+    /// Handle a malformed packet, which can't be forwarded:
+    /// - Fragments are answered with an `UnexpectedRecipient` NACK
+    /// - ACKs, NACKs and Flood responses are sent to the Simulation Controller, which can still
+    ///   deliver them to the destination
+    /// - Packets without hops or without path trace have no known destination, so they are only
+    ///   logged
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: Packet` - The malformed packet
+    /// * `error: PacketError` - The problem found in the packet
+    fn reject_packet(&mut self, packet: Packet, error: PacketError) {
+        self.log_packet(
+            format!("Rejected malformed packet: {}", error).as_str(),
+            LogLevel::ERROR,
+            &packet,
+        );
+        self.stats.update(|stats| stats.record_rejected());
+
+        if matches!(error, PacketError::EmptyHops | PacketError::EmptyPathTrace) {
+            return;
+        }
+
+        match packet.pack_type {
+            PacketType::MsgFragment(ref fragment) => {
+                let fragment_index = fragment.fragment_index;
+                self.send_nack_fragment(
+                    &packet,
+                    NackType::UnexpectedRecipient(self.id),
+                    fragment_index,
+                );
+            }
+            _ => {
+                self.shortcut(packet);
+            }
+        }
+    }
+
+    /// Set the drop rate of the link towards a neighbor, overriding the drone PDR on that link.
+    /// If the link already has a drop policy, its base rate is updated, otherwise a [`Bernoulli`]
+    /// policy is used. The rate is kept if the neighbor is removed and added again.
+    ///
+    /// ### Arguments
+    ///
+    /// * `node_id: NodeId` - The ID of the neighbor
+    /// * `pdr: f32` - The drop rate of the link, saturated to `0.0..1.0`
+    pub fn set_link_pdr(&mut self, node_id: NodeId, pdr: f32) {
+        let pdr = saturate_pdr(pdr);
+        match self.link_policies.get_mut(&node_id) {
+            Some(policy) => policy.set_rate(pdr),
+            None => {
+                self.link_policies
+                    .insert(node_id, Box::new(Bernoulli::new(pdr)));
+            }
+        }
+    }
+
+    /// Set the drop policy of the link towards a neighbor, overriding the drone policy on that link.
+    pub fn set_link_drop_policy<P: DropPolicy + 'static>(&mut self, node_id: NodeId, policy: P) {
+        self.link_policies.insert(node_id, Box::new(policy));
+    }
+
+    /// Remove the drop policy of the link towards a neighbor, so that the drone policy is used.
+    pub fn clear_link_pdr(&mut self, node_id: NodeId) {
+        self.link_policies.remove(&node_id);
+    }
+
+    /// Check whether the packet should be dropped, consulting the drop policy of the link, or the
+    /// drone policy if the link has none.
+    ///
+    /// ### Arguments
+    ///
+    /// * `next_hop: NodeId` - The neighbor the packet is sent to
+    /// ---
+    /// ### Returns
+    /// `true` if the packet should be dropped, `false` otherwise
+    fn should_drop(&mut self, next_hop: NodeId) -> bool {
+        let dropped = match self.link_policies.get_mut(&next_hop) {
+            Some(policy) => policy.should_drop(&mut *self.rng),
+            None => self.drop_policy.should_drop(&mut *self.rng),
+        };
+        self.stats
+            .update(|stats| stats.record_drop_check(next_hop, dropped));
+        dropped
+    }
+
+    /// Forwards a packet to the next node, doing checks such as:
+    /// 1. The current drone is the intended receiver
+    /// 2. Check that the drone is not the last hop
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: Packet` - The packet to forward
+    /// * `skip_pdr_check: bool` - If it should skip the check for the Packet Drop Rate before sending.
+    ///   True for ACKs, NACKs, Flood messages
+    /// * `fragment_index: u64` - Id of the fragment to forward
+    fn forward_packet(&mut self, mut packet: Packet, skip_pdr_check: bool, fragment_index: u64) {
+        // Step 1: check I'm the intended receiver
+        let curr_hop = packet.routing_header.hops[packet.routing_header.hop_index];
+        if self.id != curr_hop {
+            // Error, I'm not the one who's supposed to receive this
+            self.handle_routing_error(
+                packet,
+                NackType::UnexpectedRecipient(self.id),
+                fragment_index,
+            );
+            return;
+        }
+
+        // Step 2: check I'm not the last hop
+        let next_hop_index = packet.routing_header.hop_index + 1;
+        if next_hop_index >= packet.routing_header.hops.len() {
+            // Error, I'm the last hop!
+            self.handle_routing_error(packet, NackType::DestinationIsDrone, fragment_index);
+            return;
+        }
+
+        // Step 3: increase the hop index. The packet is moved to the next hop, never copied
+        packet.routing_header.hop_index = next_hop_index;
+
+        // Skip_pdr_check: true only for ACK and NACK, so in those two cases we don't send the ACK back to the sender.
+        self.send_packet(packet, skip_pdr_check, fragment_index);
+    }
+
+    /// Handle a packet that can't be forwarded because of its routing header. Fragments are
+    /// answered with a NACK, while ACKs, NACKs and Flood responses can't be NACKed, so they are
+    /// delivered through the Simulation Controller.
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: Packet` - The packet, as it was received
+    /// * `nack_type: NackType` - The routing error
+    /// * `fragment_index: u64` - Id of the fragment, if the packet is a fragment
+    fn handle_routing_error(&mut self, packet: Packet, nack_type: NackType, fragment_index: u64) {
+        match packet.pack_type {
+            PacketType::MsgFragment(_) => {
+                self.send_nack_fragment(&packet, nack_type, fragment_index);
+            }
+            _ => {
+                self.log_packet(
+                    format!("Routing error {:?}, sent to the controller", nack_type).as_str(),
+                    LogLevel::INFO,
+                    &packet,
+                );
+                self.shortcut(packet);
+            }
+        }
+    }
+
+    /// Send a packet to the target's channel. The target is the next node in the routing header.
+    ///
+    /// ### Arguments
+    ///
+    /// * `mut packet: Packet` - The packet to send
+    /// * `skip_pdr_check: bool` - If it should skip the check for the Packet Drop Rate before sending.
+    ///   True for ACKs, NACKs, Flood messages
+    /// * `fragment_index: u64` - Id of the fragment to forward
+    ///
+    /// ---
+    /// ### Returns
+    /// `true` if the packet is handed to the runtime to be sent, `false` otherwise.
+    fn send_packet(
+        &mut self,
+        mut packet: Packet,
+        skip_pdr_check: bool,
+        fragment_index: u64,
+    ) -> bool {
+        let next_hop_index = packet.routing_header.hop_index;
+
+        // Check if the next_hop_index is valid
+        let Some(&next_hop) = packet.routing_header.hops.get(next_hop_index) else {
+            self.log_packet(
+                format!(
+                    "Error: next_hop_index ({}) >= packet.routing_header.hops.len() ({})",
+                    next_hop_index,
+                    packet.routing_header.hops.len()
+                )
+                .as_str(),
+                LogLevel::ERROR,
+                &packet,
+            );
+            self.shortcut(packet);
+            return false;
+        };
+
+        // Check if packet can be dropped, if so check the PDR
+        if !skip_pdr_check && self.neighbors.contains(&next_hop) && self.should_drop(next_hop) {
+            // Packet dropped, the NACK is created from the packet as it was received, so that the
+            // packet itself can be moved to the event
+            packet.routing_header.hop_index -= 1;
+            let nack = self.create_nack(&packet, NackType::Dropped, fragment_index);
+            packet.routing_header.hop_index += 1;
+
+            // Notify controller that a packet has been dropped
+            self.actions
+                .push(Action::Event(DroneEvent::PacketDropped(packet)));
+            self.send_packet(nack, true, 0);
+
+            return false;
+        }
+
+        self.egress(packet, next_hop)
+    }
+
+    /// Hand a packet to the runtime, to be sent to a neighbor. When the next hop is not a
+    /// neighbor, the packet is handled as if the neighbor was disconnected.
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: Packet` - The packet to send
+    /// * `next_hop: NodeId` - The neighbor the packet is sent to
+    /// ---
+    /// ### Returns
+    /// `true` if the packet is handed to the runtime, `false` otherwise
+    fn egress(&mut self, packet: Packet, next_hop: NodeId) -> bool {
+        if !self.neighbors.contains(&next_hop) {
+            self.log_packet(
+                format!(
+                    "Error: next_hop ({}) is not a neighbor of drone {}",
+                    next_hop, self.id
+                )
+                .as_str(),
+                LogLevel::ERROR,
+                &packet,
+            );
+            self.undeliverable(packet, next_hop);
+            return false;
+        }
+
+        self.actions.push(Action::Send {
+            to: next_hop,
+            packet,
+        });
+        true
+    }
+
+    /// Handle a packet that can't reach its next hop in any way. Fragments are answered with an
+    /// `ErrorInRouting` NACK, so that the sender finds another route, and flood requests are lost.
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: Packet` - The packet, with the hop index pointing to the next hop
+    /// * `next_hop: NodeId` - The neighbor that can't be reached
+    fn undeliverable(&mut self, packet: Packet, next_hop: NodeId) {
+        if let PacketType::FloodRequest(ref request) = packet.pack_type {
+            self.end_flood_branch(request.initiator_id, request.flood_id, false);
+            return;
+        }
+
+        let Some(mut packet) = self.shortcut(packet) else {
+            return;
+        };
+        if let PacketType::MsgFragment(ref fragment) = packet.pack_type {
+            let fragment_index = fragment.fragment_index;
+            packet.routing_header.hop_index -= 1;
+            self.send_nack_fragment(&packet, NackType::ErrorInRouting(next_hop), fragment_index);
+        }
+    }
+
+    /// Drop a packet that doesn't fit in the channel of a neighbor.
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: Packet` - The packet, with the hop index pointing to the next hop
+    /// * `next_hop: NodeId` - The neighbor with the full channel
+    fn congestion_drop(&mut self, packet: Packet, next_hop: NodeId) {
+        self.log_packet(
+            format!("Channel of {} full, packet dropped", next_hop).as_str(),
+            LogLevel::INFO,
+            &packet,
+        );
+        self.stats.update(|stats| stats.record_congestion_drop());
+
+        match packet.pack_type {
+            PacketType::MsgFragment(ref fragment) => {
+                // The NACK is created from the packet as it was received, so that the packet
+                // itself can be moved to the event
+                let fragment_index = fragment.fragment_index;
+                let mut packet = packet;
+                packet.routing_header.hop_index -= 1;
+                let nack = self.create_nack(&packet, NackType::Dropped, fragment_index);
+                packet.routing_header.hop_index += 1;
+
+                self.actions
+                    .push(Action::Event(DroneEvent::PacketDropped(packet)));
+                self.send_packet(nack, true, 0);
+            }
+            // Lost, as if the link dropped it: the branch of the flood ends here
+            PacketType::FloodRequest(ref request) => {
+                self.end_flood_branch(request.initiator_id, request.flood_id, true);
+            }
+            _ => {
+                self.shortcut(packet);
+            }
+        }
+    }
+
+    /// Deliver a packet that can't reach its next hop through the Simulation Controller.
+    /// Only ACKs, NACKs and Flood responses can be delivered this way.
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: Packet` - The packet to deliver
+    /// ---
+    /// ### Returns
+    /// The packet if it can't be delivered through the Simulation Controller
+    fn shortcut(&mut self, packet: Packet) -> Option<Packet> {
+        match packet.pack_type {
+            PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_) => {
+                self.stats.update(|stats| stats.record_shortcut());
+                self.actions.push(Action::Shortcut(packet));
+                None
+            }
+            PacketType::MsgFragment(_) | PacketType::FloodRequest(_) => Some(packet),
+        }
+    }
+
+    /// Send a NACK packet to the previous node. The target is taken by reversing
+    /// the routing header, starting from the current hop.
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: &Packet` - The packet that couldn't be sent, as it was received
+    /// * `nack_type: NackType` - The cause for the loss of the packet (Drop, ErrorInRouting, ...)
+    /// * `fragment_index: u64` - Id of the packet that couldn't be sent
+    fn send_nack_fragment(&mut self, packet: &Packet, nack_type: NackType, fragment_index: u64) {
+        let nack = self.create_nack(packet, nack_type, fragment_index);
+
+        // If the NACK can't be forwarded, it's sent to the SC
+        self.send_packet(nack, true, 0);
+    }
+
+    /// Create the NACK of a packet, routed back to the node that sent the packet.
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: &Packet` - The packet that couldn't be sent, as it was received
+    /// * `nack_type: NackType` - The cause for the loss of the packet (Drop, ErrorInRouting, ...)
+    /// * `fragment_index: u64` - Id of the packet that couldn't be sent
+    fn create_nack(&self, packet: &Packet, nack_type: NackType, fragment_index: u64) -> Packet {
+        self.stats.update(|stats| stats.record_nack(&nack_type));
+
+        let nack = wg_2024::packet::Nack {
+            fragment_index,
+            nack_type,
+        };
+
+        Packet {
+            pack_type: PacketType::Nack(nack),
+            session_id: packet.session_id,
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: self.reverse_route(&packet.routing_header),
+            },
+        }
+    }
+
+    /// Handles a flood request packet, follows these steps:
+    /// 1. The drone adds itself to the path_trace
+    /// 2. If the ID is in the memory: create and send a FloodResponse
+    /// 3. Otherwise:
+    ///     - If has neighbors forwards the packet to its neighbors
+    ///     - If no neighbors (or no neighbor can be reached) send a FloodResponse to the node from
+    ///       which it received it
+    pub(crate) fn handle_flood_req(
+        &mut self,
+        mut packet: FloodRequest,
+        session_id: u64,
+        routing_header: SourceRoutingHeader,
+    ) {
+        // If we have already have the flood id from that sender
+        // Request already handled, prepare response
+        let flood_from_sender =
+            self.flood_cache
+                .contains(packet.initiator_id, packet.flood_id, self.now());
+        if flood_from_sender && !self.crashed {
+            // Add myself to the path trace
+            packet.path_trace.push((self.id, NodeType::Drone));
+            self.send_flood_response(packet, session_id);
+            return;
+        }
+
+        // Send to neighbors
+        // Save the last node's ID, we don't want to send the request to it
+        // Validation rejects empty path traces, but `handle_flood_req` is public
+        let Some(&(last_node, _)) = packet.path_trace.last() else {
+            self.log(
+                "Can't forward a flood request without sender",
+                LogLevel::ERROR,
+            );
+            return;
+        };
+
+        // Only put myself in the path trace if I'm not crashed
+        if !self.crashed {
+            // Add our ID to the trace
+            packet.path_trace.push((self.id, NodeType::Drone));
+        }
+
+        self.flood_cache
+            .insert(packet.initiator_id, packet.flood_id, self.now());
+
+        // Send to all neighbors, avoiding the node that sent the request to us
+        let targets: Vec<NodeId> = self
+            .neighbors
+            .iter()
+            .filter(|neighbor_id| **neighbor_id != last_node)
+            .copied()
+            .collect();
+
+        // Dead end: there is nowhere to forward the request, so the branch ends here
+        if targets.is_empty() {
+            if !self.crashed {
+                self.send_flood_response(packet, session_id);
+            }
+            return;
+        }
+
+        for &neighbor_id in &targets {
+            // The two clones here are required, since we are sending the same stuff to multiple nodes!
+            // No message sent to SC. Crashed neighbours should not be in the topology
+            self.actions.push(Action::Send {
+                to: neighbor_id,
+                packet: Packet {
+                    pack_type: PacketType::FloodRequest(packet.clone()),
+                    routing_header: routing_header.clone(),
+                    session_id,
+                },
+            });
+        }
+
+        // The drone is also a dead end if no branch can leave it, which is only known once the
+        // runtime reports the outcome of every branch
+        if !self.crashed {
+            self.floods.insert(
+                (packet.initiator_id, packet.flood_id),
+                FloodFanout {
+                    request: packet,
+                    session_id,
+                    pending: targets.len(),
+                    reached: false,
+                },
+            );
+        }
+    }
+
+    /// Record the outcome of a branch of a flood request forwarded by the drone. Once every branch
+    /// is over, if none of them left the drone, the request is answered as a dead end.
+    ///
+    /// ### Arguments
+    ///
+    /// * `initiator_id: NodeId` - The initiator of the flood request
+    /// * `flood_id: u64` - The ID of the flood
+    /// * `reached: bool` - Whether the request left the drone on this branch
+    fn end_flood_branch(&mut self, initiator_id: NodeId, flood_id: u64, reached: bool) {
+        let key = (initiator_id, flood_id);
+        let Some(fanout) = self.floods.get_mut(&key) else {
+            return;
+        };
+        fanout.pending = fanout.pending.saturating_sub(1);
+        fanout.reached |= reached;
+        if fanout.pending > 0 {
+            return;
+        }
+
+        let Some(fanout) = self.floods.remove(&key) else {
+            return;
+        };
+        if !fanout.reached && !self.crashed {
+            self.send_flood_response(fanout.request, fanout.session_id);
+        }
+    }
+
+    /// Answer a flood request with a FloodResponse, sent back to the node the request came from.
+    /// The route is the reversed path trace of the request.
+    ///
+    /// ### Arguments
+    ///
+    /// * `packet: FloodRequest` - The flood request, with the drone already added to the path trace
+    /// * `session_id: u64` - The session ID of the flood request
+    fn send_flood_response(&mut self, packet: FloodRequest, session_id: u64) {
+        let mut route: Vec<u8> = packet.path_trace.iter().map(|node| node.0).collect();
+        route.reverse();
+
+        // Get the ID of the node that sent the request
+        let Some(&sender_id) = route.get(1) else {
+            self.log(
+                "Can't answer a flood request without sender",
+                LogLevel::ERROR,
+            );
+            return;
+        };
+
+        let response = FloodResponse {
+            flood_id: packet.flood_id,
+            path_trace: packet.path_trace,
+        };
+
+        // Create the packet with the route provided in the path trace
+        let new_packet = Packet {
+            pack_type: PacketType::FloodResponse(response),
+            session_id,
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: route,
+            },
+        };
+
+        self.stats.update(|stats| stats.record_flood_response());
+        if self.send_packet(new_packet, true, 0) {
+            self.log(
+                format!("Sending response to {:?}", sender_id).as_str(),
+                LogLevel::INFO,
+            );
+        }
+    }
+
+    /// Route back to the sender of a packet received by the drone: the drone, followed by the hops
+    /// the packet went through, from the previous hop to the sender.
+    ///
+    /// The hops are taken from `hop_index`, not from the position of the drone in the header:
+    /// the drone may be missing (`UnexpectedRecipient`) or appear more than once.
+    /// If `hop_index` is 0 or out of range the previous hop is unknown, so the route goes
+    /// towards the sender directly.
+    ///
+    /// ### Arguments
+    ///
+    /// * `header: &SourceRoutingHeader` - The routing header of the packet, as it was received
+    fn reverse_route(&self, header: &SourceRoutingHeader) -> Vec<u8> {
+        let previous_hops = if header.hop_index > 0 && header.hop_index < header.hops.len() {
+            &header.hops[..header.hop_index]
+        } else {
+            header.hops.get(..1).unwrap_or_default()
+        };
+
+        let mut route = vec![self.id];
+        // Skip the drone itself, if the header says it's also the previous hop
+        route.extend(
+            previous_hops
+                .iter()
+                .rev()
+                .skip_while(|node_id| **node_id == self.id),
+        );
+        route
+    }
+
+    /// Utility method used to cleanly log information, differentiating on three different levels.
+    /// The log is written to the sink of the drone, by default the standard output.
+    ///
+    /// # Args
+    /// * `log_message: &str` - the message to log
+    /// * `level: u8` - the level of the log
+    pub fn log(&self, log_message: &str, level: LogLevel) {
+        self.log_event(log_message, level, None);
+    }
+
+    /// Same as [`DroneCore::log`], but the message refers to a packet, so the packet type
+    /// and the session ID are added to the record.
+    ///
+    /// # Args
+    /// * `log_message: &str` - the message to log
+    /// * `level: u8` - the level of the log
+    /// * `packet: &Packet` - the packet the message refers to
+    pub fn log_packet(&self, log_message: &str, level: LogLevel, packet: &Packet) {
+        let packet_info = (packet_type_name(&packet.pack_type), packet.session_id);
+        self.log_event(log_message, level, Some(packet_info));
+    }
+
+    /// Send the log to the sink of the drone, if the level of the drone allows it.
+    fn log_event(
+        &self,
+        log_message: &str,
+        level: LogLevel,
+        packet_info: Option<(&'static str, u64)>,
+    ) {
+        if !self.log_level.allows(level) {
+            return;
+        }
+
+        self.log_sink.log(&LogRecord {
+            drone_id: self.id,
+            level,
+            message: log_message,
+            packet_type: packet_info.map(|(packet_type, _)| packet_type),
+            session_id: packet_info.map(|(_, session_id)| session_id),
+        });
+    }
+}
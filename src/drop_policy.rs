@@ -0,0 +1,179 @@
+use rand::{Rng, RngCore};
+use std::collections::HashSet;
+
+/// Model used by the drone to decide whether a fragment is dropped before being sent.
+///
+/// The drone consults the policy once for every fragment it tries to send. ACKs, NACKs and flood
+/// messages are never dropped, so they never reach the policy.
+pub trait DropPolicy: Send {
+    /// Decide whether the next fragment should be dropped.
+    ///
+    /// ### Arguments
+    ///
+    /// * `rng: &mut dyn RngCore` - The random number generator of the drone
+    /// ---
+    /// ### Returns
+    /// `true` if the fragment should be dropped, `false` otherwise
+    fn should_drop(&mut self, rng: &mut dyn RngCore) -> bool;
+
+    /// Update the base drop rate of the policy. Called when the Simulation Controller sends a
+    /// `SetPacketDropRate` command, with the rate saturated to `0.0..1.0`. Policies without a
+    /// rate can ignore it.
+    fn set_rate(&mut self, _pdr: f32) {}
+}
+
+/// Independent drops: every fragment is dropped with probability `pdr`.
+/// This is the default policy of the drone.
+#[derive(Debug, Clone)]
+pub struct Bernoulli {
+    pdr: f32,
+}
+
+impl Bernoulli {
+    pub fn new(pdr: f32) -> Self {
+        Self { pdr }
+    }
+}
+
+impl DropPolicy for Bernoulli {
+    fn should_drop(&mut self, rng: &mut dyn RngCore) -> bool {
+        rng.gen_range(0.0..1.0) < self.pdr
+    }
+
+    fn set_rate(&mut self, pdr: f32) {
+        self.pdr = pdr;
+    }
+}
+
+/// Burst losses, modeled with a two-state Markov chain. In the good state fragments are dropped
+/// with the base rate, in the bad state with a (usually much higher) burst rate.
+/// Before every fragment the chain can move to the other state.
+#[derive(Debug, Clone)]
+pub struct GilbertElliott {
+    good_pdr: f32,      // Drop rate in the good state, the base rate
+    bad_pdr: f32,       // Drop rate in the bad state
+    enter_bad: f32,     // Probability of moving from the good state to the bad state
+    exit_bad: f32,      // Probability of moving from the bad state to the good state
+    in_bad_state: bool, // Whether the chain is in the bad state
+}
+
+impl GilbertElliott {
+    /// Create a new policy, starting in the good state.
+    ///
+    /// ### Arguments
+    ///
+    /// * `good_pdr: f32` - Drop rate in the good state
+    /// * `bad_pdr: f32` - Drop rate in the bad state
+    /// * `enter_bad: f32` - Probability of moving from the good state to the bad state
+    /// * `exit_bad: f32` - Probability of moving from the bad state to the good state
+    pub fn new(good_pdr: f32, bad_pdr: f32, enter_bad: f32, exit_bad: f32) -> Self {
+        Self {
+            good_pdr,
+            bad_pdr,
+            enter_bad,
+            exit_bad,
+            in_bad_state: false,
+        }
+    }
+
+    /// Whether the chain is currently in the bad state
+    pub fn in_bad_state(&self) -> bool {
+        self.in_bad_state
+    }
+}
+
+impl DropPolicy for GilbertElliott {
+    fn should_drop(&mut self, rng: &mut dyn RngCore) -> bool {
+        let transition = if self.in_bad_state {
+            self.exit_bad
+        } else {
+            self.enter_bad
+        };
+        if rng.gen_range(0.0..1.0) < transition {
+            self.in_bad_state = !self.in_bad_state;
+        }
+
+        let pdr = if self.in_bad_state {
+            self.bad_pdr
+        } else {
+            self.good_pdr
+        };
+        rng.gen_range(0.0..1.0) < pdr
+    }
+
+    fn set_rate(&mut self, pdr: f32) {
+        self.good_pdr = pdr;
+    }
+}
+
+/// Deterministic drops: every `n`-th fragment is dropped (the `n`-th, the `2n`-th, ...).
+/// With `n = 0` no fragment is dropped.
+///
+/// The rate can be any fraction: `drops` fragments out of every `out_of` are dropped, spread as
+/// evenly as possible, so that in the long run exactly that fraction is dropped.
+#[derive(Debug, Clone)]
+pub struct EveryNth {
+    drops: u64,  // Fragments dropped out of every `out_of`
+    out_of: u64, // Length of the cycle, never 0
+    seen: u64,   // Number of fragments seen by the policy
+}
+
+/// Resolution of the rates set with `set_rate`
+const RATE_SCALE: u64 = 1_000_000;
+
+impl EveryNth {
+    pub fn new(n: u64) -> Self {
+        Self {
+            drops: u64::from(n != 0),
+            out_of: n.max(1),
+            seen: 0,
+        }
+    }
+}
+
+impl DropPolicy for EveryNth {
+    /// The fragment is dropped when `seen * drops / out_of` crosses an integer.
+    fn should_drop(&mut self, _rng: &mut dyn RngCore) -> bool {
+        let before = u128::from(self.seen) * u128::from(self.drops) / u128::from(self.out_of);
+        self.seen += 1;
+        let after = u128::from(self.seen) * u128::from(self.drops) / u128::from(self.out_of);
+        after > before
+    }
+
+    /// The rate is kept as a fraction, so `0.25` drops every 4th fragment and `0.75` drops 3
+    /// fragments out of every 4. Rates outside `0.0..1.0` are saturated.
+    fn set_rate(&mut self, pdr: f32) {
+        let pdr = if pdr.is_nan() {
+            0.0
+        } else {
+            pdr.clamp(0.0, 1.0)
+        };
+        self.drops = (f64::from(pdr) * RATE_SCALE as f64).round() as u64;
+        self.out_of = RATE_SCALE;
+    }
+}
+
+/// Scripted drops: the fragments in the list are dropped, identified by the order in which they
+/// are seen by the policy, starting from 0. The base rate is ignored.
+#[derive(Debug, Clone)]
+pub struct Scripted {
+    drops: HashSet<u64>,
+    seen: u64, // Number of fragments seen by the policy
+}
+
+impl Scripted {
+    pub fn new(drops: impl IntoIterator<Item = u64>) -> Self {
+        Self {
+            drops: drops.into_iter().collect(),
+            seen: 0,
+        }
+    }
+}
+
+impl DropPolicy for Scripted {
+    fn should_drop(&mut self, _rng: &mut dyn RngCore) -> bool {
+        let drop = self.drops.contains(&self.seen);
+        self.seen += 1;
+        drop
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+
+/// Number of flood IDs remembered for each initiator, unless configured otherwise
+pub const DEFAULT_FLOOD_WINDOW: usize = 64;
+
+/// Limits of the [`FloodCache`]. A flood request is forgotten as soon as one of the limits is
+/// exceeded, after that a duplicate of it is handled as a new request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloodCacheConfig {
+    /// Maximum number of flood IDs remembered for each initiator, the oldest is forgotten first.
+    /// At least 1, so that duplicates are always suppressed
+    pub window: usize,
+    /// Maximum number of flood requests remembered in total, `None` = no limit other than the window.
+    /// At least 1, so that duplicates are always suppressed
+    pub capacity: Option<usize>,
+    /// Time after which a flood request is forgotten, `None` = never
+    pub ttl: Option<Duration>,
+}
+
+impl Default for FloodCacheConfig {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_FLOOD_WINDOW,
+            capacity: None,
+            ttl: None,
+        }
+    }
+}
+
+/// Flood IDs seen from a single initiator
+#[derive(Debug, Default)]
+struct Window {
+    ids: HashSet<u64>,                    // Contains: O(1) in average
+    order: VecDeque<(u64, u64, Instant)>, // Flood ID, insertion number and time, oldest first
+}
+
+/// Bounded memory of the flood requests handled by the drone, used to suppress duplicates.
+///
+/// Every initiator has its own sliding window of flood IDs, so a client flooding often can't
+/// push out the floods of the other initiators. Since initiators are identified by a `NodeId`,
+/// the memory is bounded even when no capacity is set.
+#[derive(Debug)]
+pub struct FloodCache {
+    config: FloodCacheConfig,
+    windows: HashMap<NodeId, Window>,
+    len: usize,    // Flood requests remembered, over all the windows
+    inserted: u64, // Flood requests inserted so far, orders the requests of different windows
+}
+
+impl FloodCache {
+    pub fn new(config: FloodCacheConfig) -> Self {
+        Self {
+            // With no flood remembered, a flood request would loop forever in a cyclic topology
+            config: FloodCacheConfig {
+                window: config.window.max(1),
+                capacity: config.capacity.map(|capacity| capacity.max(1)),
+                ..config
+            },
+            windows: HashMap::new(),
+            len: 0,
+            inserted: 0,
+        }
+    }
+
+    /// Check whether a flood request has already been seen and is still remembered.
+    ///
+    /// ### Arguments
+    ///
+    /// * `initiator_id: NodeId` - The ID of the node that started the flood
+    /// * `flood_id: u64` - The ID of the flood
+    /// * `now: Instant` - The current time, used to forget expired requests
+    pub fn contains(&mut self, initiator_id: NodeId, flood_id: u64, now: Instant) -> bool {
+        self.expire(now);
+        self.windows
+            .get(&initiator_id)
+            .is_some_and(|window| window.ids.contains(&flood_id))
+    }
+
+    /// Remember a flood request, forgetting the oldest ones if a limit is exceeded.
+    ///
+    /// ### Arguments
+    ///
+    /// * `initiator_id: NodeId` - The ID of the node that started the flood
+    /// * `flood_id: u64` - The ID of the flood
+    /// * `now: Instant` - The current time, stored to expire the request
+    pub fn insert(&mut self, initiator_id: NodeId, flood_id: u64, now: Instant) {
+        self.expire(now);
+
+        let window = self.windows.entry(initiator_id).or_default();
+        if !window.ids.insert(flood_id) {
+            return;
+        }
+        window.order.push_back((flood_id, self.inserted, now));
+        self.len += 1;
+        self.inserted += 1;
+
+        if window.order.len() > self.config.window {
+            if let Some((oldest, _, _)) = window.order.pop_front() {
+                window.ids.remove(&oldest);
+                self.len -= 1;
+            }
+        }
+
+        if let Some(capacity) = self.config.capacity {
+            while self.len > capacity {
+                self.evict_oldest();
+            }
+        }
+    }
+
+    /// Number of flood requests currently remembered
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Forget the flood requests older than the TTL
+    fn expire(&mut self, now: Instant) {
+        let Some(ttl) = self.config.ttl else {
+            return;
+        };
+
+        for window in self.windows.values_mut() {
+            while let Some(&(flood_id, _, inserted)) = window.order.front() {
+                if now.saturating_duration_since(inserted) < ttl {
+                    break;
+                }
+                window.order.pop_front();
+                window.ids.remove(&flood_id);
+                self.len -= 1;
+            }
+        }
+        self.windows.retain(|_, window| !window.order.is_empty());
+    }
+
+    /// Forget the oldest flood request, over all the windows
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .windows
+            .iter()
+            .filter_map(|(initiator_id, window)| {
+                window
+                    .order
+                    .front()
+                    .map(|(_, number, _)| (*initiator_id, *number))
+            })
+            .min_by_key(|(_, number)| *number);
+
+        if let Some((initiator_id, _)) = oldest {
+            let window = self.windows.get_mut(&initiator_id).unwrap();
+            if let Some((flood_id, _, _)) = window.order.pop_front() {
+                window.ids.remove(&flood_id);
+                self.len -= 1;
+            }
+            if window.order.is_empty() {
+                self.windows.remove(&initiator_id);
+            }
+        }
+    }
+}
@@ -0,0 +1,463 @@
+//! Build and drive networks of [`RustafarianDrone`](crate::RustafarianDrone)s in tests.
+//!
+//! A [`Topology`] describes the drones, clients and servers and the links between them, and
+//! [`Topology::start`] runs it, returning a [`Harness`] used to inject packets, send commands and
+//! collect what comes out of the network. Clients and servers are not run: the packets sent to
+//! them are collected by the harness.
+//!
+//! ### Example
+//!
+//! ```
+//! use rustafarian_drone::harness::{Topology, CLIENT, SERVER};
+//! # use wg_2024::network::SourceRoutingHeader;
+//! # use wg_2024::packet::{Fragment, Packet, PacketType};
+//! # fn fragment(hops: Vec<u8>) -> Packet {
+//! #     Packet {
+//! #         pack_type: PacketType::MsgFragment(Fragment {
+//! #             fragment_index: 0,
+//! #             total_n_fragments: 1,
+//! #             length: 128,
+//! #             data: [0; 128],
+//! #         }),
+//! #         routing_header: SourceRoutingHeader { hop_index: 1, hops },
+//! #         session_id: 1,
+//! #     }
+//! # }
+//!
+//! // Client 1 - 11 - 12 - 13 - Server 2
+//! let harness = Topology::line(3).start();
+//! harness.inject(fragment(vec![CLIENT, 11, 12, 13, SERVER]));
+//! assert!(harness.recv_default(SERVER).is_some());
+//! harness.stop();
+//! ```
+use crate::initializer::{start_network_with, Network};
+use crate::stats::DroneStats;
+use crate::{LogLevel, RustafarianDroneBuilder};
+use crossbeam_channel::Receiver;
+use std::mem;
+use std::time::Duration;
+use wg_2024::config::{Client, Config, Drone, Server};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// ID of the client of the predefined topologies
+pub const CLIENT: NodeId = 1;
+/// ID of the server of the predefined topologies
+pub const SERVER: NodeId = 2;
+/// ID of the first drone of the predefined topologies, the others follow
+pub const FIRST_DRONE: NodeId = 11;
+/// Time waited by [`Harness::recv_default`] and [`Harness::recv_event_default`]
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Drones, clients and servers of a network, with the links between them.
+///
+/// The predefined topologies number the drones from [`FIRST_DRONE`], and connect client
+/// [`CLIENT`] and server [`SERVER`] to drones far from each other. Custom topologies are built
+/// adding nodes and links, and a predefined topology can be extended the same way.
+#[derive(Debug, Clone)]
+pub struct Topology {
+    config: Config,
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Topology {
+    /// An empty topology
+    pub fn new() -> Self {
+        Self {
+            config: Config {
+                drone: Vec::new(),
+                client: Vec::new(),
+                server: Vec::new(),
+            },
+        }
+    }
+
+    /// Use an existing network configuration
+    pub fn from_config(config: Config) -> Self {
+        Self { config }
+    }
+
+    /// Client - 11 - 12 - ... - Server: `length` drones in a line
+    pub fn line(length: u8) -> Self {
+        let drones = drone_ids(length);
+        let mut topology = Self::with_drones(&drones);
+        for pair in drones.windows(2) {
+            topology = topology.link(pair[0], pair[1]);
+        }
+        topology.with_endpoints(drones[0], drones[drones.len() - 1])
+    }
+
+    /// `length` drones in a ring, at least 3, with the client and the server on opposite sides
+    pub fn ring(length: u8) -> Self {
+        assert!(length >= 3, "a ring needs at least 3 drones");
+        let drones = drone_ids(length);
+        let mut topology = Self::with_drones(&drones);
+        for (index, id) in drones.iter().enumerate() {
+            topology = topology.link(*id, drones[(index + 1) % drones.len()]);
+        }
+        topology.with_endpoints(drones[0], drones[drones.len() / 2])
+    }
+
+    /// Drone 11 in the middle, connected to `leaves` drones. The client is on the first leaf and
+    /// the server on the last one, so their traffic crosses the middle drone
+    pub fn star(leaves: u8) -> Self {
+        let drones = drone_ids(leaves.checked_add(1).expect("too many drones in the star"));
+        let mut topology = Self::with_drones(&drones);
+        for leaf in &drones[1..] {
+            topology = topology.link(drones[0], *leaf);
+        }
+        topology.with_endpoints(drones[1], drones[drones.len() - 1])
+    }
+
+    /// Drones in a grid of `rows` x `columns`, numbered row by row, each connected to the drones
+    /// above, below, on the left and on the right. The client is in the top left corner and the
+    /// server in the bottom right one
+    pub fn mesh(rows: u8, columns: u8) -> Self {
+        let drones = drone_ids(
+            rows.checked_mul(columns)
+                .expect("too many drones in the mesh"),
+        );
+        let id = |row: u8, column: u8| drones[(row * columns + column) as usize];
+        let mut topology = Self::with_drones(&drones);
+        for row in 0..rows {
+            for column in 0..columns {
+                if row + 1 < rows {
+                    topology = topology.link(id(row, column), id(row + 1, column));
+                }
+                if column + 1 < columns {
+                    topology = topology.link(id(row, column), id(row, column + 1));
+                }
+            }
+        }
+        topology.with_endpoints(drones[0], drones[drones.len() - 1])
+    }
+
+    /// A tree of drones with `depth` levels below the root, where every drone that is not a leaf
+    /// has `children` children. Drones are numbered level by level. The client is on the root
+    /// and the server on the last leaf
+    pub fn tree(depth: u8, children: u8) -> Self {
+        let count: u32 = (0..=depth as u32)
+            .map(|level| (children as u32).pow(level))
+            .sum();
+        let count = u8::try_from(count).expect("too many drones in the tree");
+        let drones = drone_ids(count);
+        let mut topology = Self::with_drones(&drones);
+        // Children of the drone at index i are at indexes i * children + 1 ..= i * children + children
+        for (index, id) in drones.iter().enumerate().skip(1) {
+            let parent = (index - 1) / children as usize;
+            topology = topology.link(drones[parent], *id);
+        }
+        topology.with_endpoints(drones[0], drones[drones.len() - 1])
+    }
+
+    /// Add a drone, without neighbors and with PDR 0
+    pub fn drone(mut self, id: NodeId) -> Self {
+        self.config.drone.push(Drone {
+            id,
+            connected_node_ids: Vec::new(),
+            pdr: 0.0,
+        });
+        self
+    }
+
+    /// Add a client, without neighbors
+    pub fn client(mut self, id: NodeId) -> Self {
+        self.config.client.push(Client {
+            id,
+            connected_drone_ids: Vec::new(),
+        });
+        self
+    }
+
+    /// Add a server, without neighbors
+    pub fn server(mut self, id: NodeId) -> Self {
+        self.config.server.push(Server {
+            id,
+            connected_drone_ids: Vec::new(),
+        });
+        self
+    }
+
+    /// Connect two nodes in both directions. Both nodes must already be in the topology.
+    pub fn link(mut self, a: NodeId, b: NodeId) -> Self {
+        for (from, to) in [(a, b), (b, a)] {
+            let neighbors = self.neighbors_mut(from);
+            if !neighbors.contains(&to) {
+                neighbors.push(to);
+            }
+        }
+        self
+    }
+
+    /// Set the PDR of every drone
+    pub fn pdr(mut self, pdr: f32) -> Self {
+        for drone in &mut self.config.drone {
+            drone.pdr = pdr;
+        }
+        self
+    }
+
+    /// Set the PDR of a single drone
+    pub fn drone_pdr(mut self, id: NodeId, pdr: f32) -> Self {
+        if let Some(drone) = self.config.drone.iter_mut().find(|drone| drone.id == id) {
+            drone.pdr = pdr;
+        }
+        self
+    }
+
+    /// The network configuration describing the topology
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Neighbors of a node, in the order the links were added
+    pub fn neighbors(&self, id: NodeId) -> &[NodeId] {
+        self.config
+            .drone
+            .iter()
+            .find(|drone| drone.id == id)
+            .map(|drone| &drone.connected_node_ids)
+            .or_else(|| {
+                self.config
+                    .client
+                    .iter()
+                    .find(|client| client.id == id)
+                    .map(|client| &client.connected_drone_ids)
+            })
+            .or_else(|| {
+                self.config
+                    .server
+                    .iter()
+                    .find(|server| server.id == id)
+                    .map(|server| &server.connected_drone_ids)
+            })
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Start a drone thread for every drone, with logging disabled.
+    pub fn start(self) -> Harness {
+        self.start_with(|builder| builder)
+    }
+
+    /// Same as [`Topology::start`], but every drone builder is passed to `configure` before the
+    /// drone is created, so that additional settings can be applied. Logging is disabled unless
+    /// `configure` sets a log level.
+    pub fn start_with<F>(self, configure: F) -> Harness
+    where
+        F: Fn(RustafarianDroneBuilder) -> RustafarianDroneBuilder,
+    {
+        let network = start_network_with(&self.config, |builder| {
+            configure(builder.log_level(LogLevel::NONE))
+        });
+        Harness {
+            network,
+            topology: self,
+            stopped: false,
+        }
+    }
+
+    fn with_drones(ids: &[NodeId]) -> Self {
+        ids.iter()
+            .fold(Self::new(), |topology, id| topology.drone(*id))
+    }
+
+    fn with_endpoints(self, client_drone: NodeId, server_drone: NodeId) -> Self {
+        self.client(CLIENT)
+            .server(SERVER)
+            .link(CLIENT, client_drone)
+            .link(SERVER, server_drone)
+    }
+
+    fn neighbors_mut(&mut self, id: NodeId) -> &mut Vec<NodeId> {
+        if let Some(drone) = self.config.drone.iter_mut().find(|drone| drone.id == id) {
+            return &mut drone.connected_node_ids;
+        }
+        if let Some(client) = self.config.client.iter_mut().find(|client| client.id == id) {
+            return &mut client.connected_drone_ids;
+        }
+        if let Some(server) = self.config.server.iter_mut().find(|server| server.id == id) {
+            return &mut server.connected_drone_ids;
+        }
+        panic!("node {} is not in the topology", id);
+    }
+}
+
+/// IDs of `count` drones, starting from [`FIRST_DRONE`]
+fn drone_ids(count: u8) -> Vec<NodeId> {
+    assert!(count > 0, "a topology needs at least one drone");
+    assert!(
+        count as usize <= (NodeId::MAX - FIRST_DRONE) as usize + 1,
+        "too many drones"
+    );
+    (0..count).map(|index| FIRST_DRONE + index).collect()
+}
+
+/// A running network, started from a [`Topology`].
+///
+/// Dropping the harness crashes every drone, without waiting for the threads to end: use
+/// [`Harness::stop`] to wait for them.
+pub struct Harness {
+    network: Network,
+    topology: Topology,
+    stopped: bool,
+}
+
+impl Harness {
+    /// Send a packet to a node, as one of its neighbors would.
+    ///
+    /// ### Arguments
+    ///
+    /// * `node: NodeId` - The node receiving the packet, a drone, a client or a server
+    /// * `packet: Packet` - The packet to send
+    pub fn send(&self, node: NodeId, packet: Packet) {
+        let sender = self
+            .network
+            .packet_senders
+            .get(&node)
+            .unwrap_or_else(|| panic!("node {} is not in the network", node));
+        // A drone that already stopped doesn't receive anything, the packet is lost
+        let _ = sender.send(packet);
+    }
+
+    /// Send a packet to the node it's routed to, the one at `hop_index` in its routing header.
+    /// Flood requests are not routed, use [`Harness::send`] for them.
+    pub fn inject(&self, packet: Packet) {
+        let header = &packet.routing_header;
+        let node = *header
+            .hops
+            .get(header.hop_index)
+            .expect("the packet is not routed to any node");
+        self.send(node, packet);
+    }
+
+    /// Send a command to a drone. Commands sent to a drone that already stopped are ignored
+    pub fn command(&self, drone: NodeId, command: DroneCommand) {
+        let sender = self
+            .network
+            .drone_commands
+            .get(&drone)
+            .unwrap_or_else(|| panic!("drone {} is not in the network", drone));
+        let _ = sender.send(command);
+    }
+
+    /// Crash a drone as the Simulation Controller would: the drone is crashed, then removed from
+    /// its neighbors, and its neighbors from it.
+    pub fn crash(&mut self, drone: NodeId) {
+        self.command(drone, DroneCommand::Crash);
+        for neighbor in self.topology.neighbors(drone).to_vec() {
+            if self.network.drone_commands.contains_key(&neighbor) {
+                self.command(neighbor, DroneCommand::RemoveSender(drone));
+            }
+            for endpoint in self
+                .network
+                .clients
+                .get_mut(&neighbor)
+                .into_iter()
+                .chain(self.network.servers.get_mut(&neighbor))
+            {
+                endpoint.neighbors.remove(&drone);
+            }
+            self.command(drone, DroneCommand::RemoveSender(neighbor));
+        }
+    }
+
+    /// Wait for a packet delivered to a client or a server.
+    ///
+    /// ### Arguments
+    ///
+    /// * `node: NodeId` - The client or the server
+    /// * `timeout: Duration` - How long to wait for the packet
+    /// ---
+    /// ### Returns
+    /// The packet, `None` if nothing is delivered in time
+    pub fn recv(&self, node: NodeId, timeout: Duration) -> Option<Packet> {
+        self.endpoint_recv(node).recv_timeout(timeout).ok()
+    }
+
+    /// Same as [`Harness::recv`], waiting for [`DEFAULT_TIMEOUT`]
+    pub fn recv_default(&self, node: NodeId) -> Option<Packet> {
+        self.recv(node, DEFAULT_TIMEOUT)
+    }
+
+    /// Packets delivered to a client or a server and not read yet, without waiting
+    pub fn delivered(&self, node: NodeId) -> Vec<Packet> {
+        self.endpoint_recv(node).try_iter().collect()
+    }
+
+    /// Wait for an event sent by any drone, `None` if nothing is sent in time
+    pub fn recv_event(&self, timeout: Duration) -> Option<DroneEvent> {
+        self.network.drone_events.recv_timeout(timeout).ok()
+    }
+
+    /// Same as [`Harness::recv_event`], waiting for [`DEFAULT_TIMEOUT`]
+    pub fn recv_event_default(&self) -> Option<DroneEvent> {
+        self.recv_event(DEFAULT_TIMEOUT)
+    }
+
+    /// Events sent by the drones and not read yet, without waiting
+    pub fn events(&self) -> Vec<DroneEvent> {
+        self.network.drone_events.try_iter().collect()
+    }
+
+    /// A copy of the statistics of a drone
+    pub fn stats(&self, drone: NodeId) -> DroneStats {
+        self.network
+            .stats
+            .get(&drone)
+            .unwrap_or_else(|| panic!("drone {} is not in the network", drone))
+            .snapshot()
+    }
+
+    /// The topology the network was started from
+    pub fn topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    /// The channels of the network, for anything the harness doesn't cover
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Crash every drone and wait until all the drone threads end. The packets still in the
+    /// network are handled as crashed drones do.
+    pub fn stop(mut self) {
+        self.shutdown();
+        for (_, handle) in mem::take(&mut self.network.handles) {
+            handle.join().expect("a drone thread panicked");
+        }
+    }
+
+    /// Crash every drone and remove all its neighbors, so that its thread ends
+    fn shutdown(&mut self) {
+        if mem::replace(&mut self.stopped, true) {
+            return;
+        }
+        for drone in &self.topology.config.drone {
+            self.command(drone.id, DroneCommand::Crash);
+            for neighbor in &drone.connected_node_ids {
+                self.command(drone.id, DroneCommand::RemoveSender(*neighbor));
+            }
+        }
+    }
+
+    fn endpoint_recv(&self, node: NodeId) -> &Receiver<Packet> {
+        self.network
+            .clients
+            .get(&node)
+            .or_else(|| self.network.servers.get(&node))
+            .map(|endpoint| &endpoint.packet_recv)
+            .unwrap_or_else(|| panic!("node {} is not a client or a server", node))
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
@@ -0,0 +1,301 @@
+use crate::stats::StatsHandle;
+use crate::RustafarianDroneBuilder;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::thread::JoinHandle;
+use std::{fs, io, thread};
+use wg_2024::config::Config;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Error returned when a network configuration can't be loaded
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The configuration file couldn't be read
+    Io(io::Error),
+    /// The configuration is not a valid wg_2024 TOML file
+    Parse(toml::de::Error),
+    /// Two nodes share the same ID
+    DuplicateNode(NodeId),
+    /// A node is connected to a node that is not defined. Contains: (node, unknown neighbor)
+    UnknownNode(NodeId, NodeId),
+    /// A node is connected to itself
+    SelfLink(NodeId),
+    /// A node lists a neighbor that doesn't list it back. Contains: (node, neighbor)
+    OneSidedLink(NodeId, NodeId),
+    /// A client or a server is connected to a node that is not a drone. Contains: (client or
+    /// server, neighbor)
+    EndpointLink(NodeId, NodeId),
+    /// A client is not connected to one or two drones. Contains: (client, number of drones)
+    ClientLinks(NodeId, usize),
+    /// A server is connected to less than two drones. Contains: (server, number of drones)
+    ServerLinks(NodeId, usize),
+    /// The PDR of a drone is not in `0.0..=1.0`. Contains: (drone, PDR)
+    InvalidPdr(NodeId, f32),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(error) => write!(f, "couldn't read the configuration: {}", error),
+            ConfigError::Parse(error) => write!(f, "invalid configuration: {}", error),
+            ConfigError::DuplicateNode(id) => write!(f, "node {} is defined more than once", id),
+            ConfigError::UnknownNode(id, neighbor) => {
+                write!(f, "node {} is connected to unknown node {}", id, neighbor)
+            }
+            ConfigError::SelfLink(id) => write!(f, "node {} is connected to itself", id),
+            ConfigError::OneSidedLink(id, neighbor) => write!(
+                f,
+                "node {} is connected to node {}, which is not connected back",
+                id, neighbor
+            ),
+            ConfigError::EndpointLink(id, neighbor) => write!(
+                f,
+                "client or server {} is connected to node {}, which is not a drone",
+                id, neighbor
+            ),
+            ConfigError::ClientLinks(id, count) => write!(
+                f,
+                "client {} is connected to {} drones, it must be connected to 1 or 2",
+                id, count
+            ),
+            ConfigError::ServerLinks(id, count) => write!(
+                f,
+                "server {} is connected to {} drones, it must be connected to at least 2",
+                id, count
+            ),
+            ConfigError::InvalidPdr(id, pdr) => {
+                write!(f, "drone {} has PDR {}, it must be in 0.0..=1.0", id, pdr)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Channels of a client or a server, which are not run by this crate
+pub struct NodeChannels {
+    /// Receive the packets sent to the node
+    pub packet_recv: Receiver<Packet>,
+    /// Send packets to the drones connected to the node
+    pub neighbors: HashMap<NodeId, Sender<Packet>>,
+}
+
+/// A running network, containing everything a Simulation Controller needs to interact with it
+pub struct Network {
+    /// Send commands to the drones. The key is the ID of the drone
+    pub drone_commands: HashMap<NodeId, Sender<DroneCommand>>,
+    /// Receive the events sent by every drone
+    pub drone_events: Receiver<DroneEvent>,
+    /// Send packets to any node of the network. The key is the ID of the node
+    pub packet_senders: HashMap<NodeId, Sender<Packet>>,
+    /// Channels of the clients. The key is the ID of the client
+    pub clients: HashMap<NodeId, NodeChannels>,
+    /// Channels of the servers. The key is the ID of the server
+    pub servers: HashMap<NodeId, NodeChannels>,
+    /// Handles of the drone threads. The key is the ID of the drone
+    pub handles: HashMap<NodeId, JoinHandle<()>>,
+    /// Statistics of the drones. The key is the ID of the drone
+    pub stats: HashMap<NodeId, StatsHandle>,
+}
+
+/// Read and parse a wg_2024 network configuration from a TOML file.
+pub fn load_config(path: &str) -> Result<Config, ConfigError> {
+    let content = fs::read_to_string(path).map_err(ConfigError::Io)?;
+    parse_config(&content)
+}
+
+/// Parse a wg_2024 network configuration from a TOML string, checking that it describes a valid
+/// network: the rules are listed in `validate_config`.
+pub fn parse_config(content: &str) -> Result<Config, ConfigError> {
+    let config: Config = toml::from_str(content).map_err(ConfigError::Parse)?;
+    validate_config(&config)?;
+    Ok(config)
+}
+
+/// Check that a configuration follows the rules of the wg_2024 protocol:
+/// - Node IDs are unique, and every connection refers to an existing node
+/// - No node is connected to itself, and every connection is listed by both nodes
+/// - Clients and servers are only connected to drones: a client to one or two, a server to at
+///   least two
+/// - The PDR of every drone is in `0.0..=1.0`
+fn validate_config(config: &Config) -> Result<(), ConfigError> {
+    let mut ids = Vec::new();
+    let ids_iter = config
+        .drone
+        .iter()
+        .map(|drone| drone.id)
+        .chain(config.client.iter().map(|client| client.id))
+        .chain(config.server.iter().map(|server| server.id));
+    for id in ids_iter {
+        if ids.contains(&id) {
+            return Err(ConfigError::DuplicateNode(id));
+        }
+        ids.push(id);
+    }
+
+    let connections: HashMap<NodeId, &Vec<NodeId>> = config
+        .drone
+        .iter()
+        .map(|drone| (drone.id, &drone.connected_node_ids))
+        .chain(
+            config
+                .client
+                .iter()
+                .map(|client| (client.id, &client.connected_drone_ids)),
+        )
+        .chain(
+            config
+                .server
+                .iter()
+                .map(|server| (server.id, &server.connected_drone_ids)),
+        )
+        .collect();
+    for id in &ids {
+        for neighbor in connections[id] {
+            if neighbor == id {
+                return Err(ConfigError::SelfLink(*id));
+            }
+            let Some(back) = connections.get(neighbor) else {
+                return Err(ConfigError::UnknownNode(*id, *neighbor));
+            };
+            if !back.contains(id) {
+                return Err(ConfigError::OneSidedLink(*id, *neighbor));
+            }
+        }
+    }
+
+    let is_drone = |id: &NodeId| config.drone.iter().any(|drone| drone.id == *id);
+    let endpoints = config
+        .client
+        .iter()
+        .map(|client| (client.id, &client.connected_drone_ids))
+        .chain(
+            config
+                .server
+                .iter()
+                .map(|server| (server.id, &server.connected_drone_ids)),
+        );
+    for (id, neighbors) in endpoints {
+        if let Some(neighbor) = neighbors.iter().find(|neighbor| !is_drone(neighbor)) {
+            return Err(ConfigError::EndpointLink(id, *neighbor));
+        }
+    }
+    for client in &config.client {
+        let count = client.connected_drone_ids.len();
+        if !(1..=2).contains(&count) {
+            return Err(ConfigError::ClientLinks(client.id, count));
+        }
+    }
+    for server in &config.server {
+        let count = server.connected_drone_ids.len();
+        if count < 2 {
+            return Err(ConfigError::ServerLinks(server.id, count));
+        }
+    }
+
+    for drone in &config.drone {
+        if !(0.0..=1.0).contains(&drone.pdr) {
+            return Err(ConfigError::InvalidPdr(drone.id, drone.pdr));
+        }
+    }
+
+    Ok(())
+}
+
+/// Start a network from a configuration: create the channels of every node and spawn a thread
+/// for every drone, using the PDR and the neighbors in the configuration.
+pub fn start_network(config: &Config) -> Network {
+    start_network_with(config, |builder| builder)
+}
+
+/// Same as [`start_network`], but every drone builder is passed to `configure` before the drone
+/// is created, so that additional settings (log level, seed, ...) can be applied.
+pub fn start_network_with<F>(config: &Config, configure: F) -> Network
+where
+    F: Fn(RustafarianDroneBuilder) -> RustafarianDroneBuilder,
+{
+    let (event_send, event_recv) = unbounded();
+
+    // Create a channel for every node
+    let mut packet_senders = HashMap::new();
+    let mut packet_receivers = HashMap::new();
+    let node_ids = config
+        .drone
+        .iter()
+        .map(|drone| drone.id)
+        .chain(config.client.iter().map(|client| client.id))
+        .chain(config.server.iter().map(|server| server.id));
+    for id in node_ids {
+        let (send, recv) = unbounded::<Packet>();
+        packet_senders.insert(id, send);
+        packet_receivers.insert(id, recv);
+    }
+
+    let neighbors_of = |ids: &Vec<NodeId>| -> HashMap<NodeId, Sender<Packet>> {
+        ids.iter()
+            .filter_map(|id| packet_senders.get(id).map(|send| (*id, send.clone())))
+            .collect()
+    };
+
+    let mut drone_commands = HashMap::new();
+    let mut handles = HashMap::new();
+    let mut stats = HashMap::new();
+    for drone_config in &config.drone {
+        let (command_send, command_recv) = unbounded();
+        drone_commands.insert(drone_config.id, command_send);
+
+        let builder = RustafarianDroneBuilder::new(
+            drone_config.id,
+            event_send.clone(),
+            command_recv,
+            packet_receivers[&drone_config.id].clone(),
+            neighbors_of(&drone_config.connected_node_ids),
+        )
+        .pdr(drone_config.pdr);
+        let mut drone = configure(builder).build();
+        stats.insert(drone_config.id, drone.stats());
+
+        let handle = thread::spawn(move || {
+            drone.run();
+        });
+        handles.insert(drone_config.id, handle);
+    }
+
+    let clients = config
+        .client
+        .iter()
+        .map(|client| {
+            let channels = NodeChannels {
+                packet_recv: packet_receivers[&client.id].clone(),
+                neighbors: neighbors_of(&client.connected_drone_ids),
+            };
+            (client.id, channels)
+        })
+        .collect();
+
+    let servers = config
+        .server
+        .iter()
+        .map(|server| {
+            let channels = NodeChannels {
+                packet_recv: packet_receivers[&server.id].clone(),
+                neighbors: neighbors_of(&server.connected_drone_ids),
+            };
+            (server.id, channels)
+        })
+        .collect();
+
+    Network {
+        drone_commands,
+        drone_events: event_recv,
+        packet_senders,
+        clients,
+        servers,
+        handles,
+        stats,
+    }
+}
@@ -1,28 +1,52 @@
 #![allow(unused)]
-use crossbeam_channel::{select_biased, unbounded, Receiver, Sender};
+use crate::capture::{previous_hop, CaptureRecord, CaptureWriter, Direction};
+use crate::congestion::{FullQueuePolicy, OverflowQueues};
+use crate::drone_core::{Action, DroneCore, SendFailure};
+use crate::drop_policy::DropPolicy;
+use crate::priority::PriorityInbox;
+use crate::stats::{StatsHandle, StopReason};
+use crossbeam_channel::{
+    never, select_biased, unbounded, Receiver, SendError, SendTimeoutError, Sender, TrySendError,
+};
 use rand::*;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::env;
-use std::env::VarError;
-use std::ops::Index;
-use std::{fs, thread};
-use wg_2024::config::Config;
+use std::time::Duration;
 use wg_2024::controller::{DroneCommand, DroneEvent};
 use wg_2024::drone::Drone;
 use wg_2024::network::{NodeId, SourceRoutingHeader};
-use wg_2024::packet::PacketType::Nack;
-use wg_2024::packet::{Ack, FloodRequest, FloodResponse, NackType, NodeType};
+use wg_2024::packet::FloodRequest;
 use wg_2024::packet::{Packet, PacketType};
+mod builder;
+pub mod capture;
+mod command;
+pub mod congestion;
+pub mod drone_core;
+pub mod drop_policy;
+pub mod flood_cache;
+#[cfg(any(test, feature = "harness"))]
+pub mod harness;
+pub mod initializer;
+pub mod logging;
+pub mod priority;
+pub mod replay;
+pub mod simulator;
+pub mod stats;
 mod tests;
+pub mod validation;
+
+pub use builder::RustafarianDroneBuilder;
+pub use command::RustafarianCommand;
 
 const LOG_ENV_VAR: &str = "RUSTAFARIAN_LOG_LEVEL";
+const SEED_ENV_VAR: &str = "RUSTAFARIAN_SEED";
 /// Value used from the log method to filter the log messages:
 ///
 /// * `NONE`: logging is disabled
 /// * `ERROR`: only log errors, to `std::error`
 /// * `INFO`: log errors and basic information
 /// * `DEBUG`: used for debug, will log every information
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LogLevel {
     NONE,
     ERROR,
@@ -30,53 +54,36 @@ pub enum LogLevel {
     DEBUG,
 }
 
-pub struct RustafarianDrone {
-    id: NodeId,                                 // The ID of the drone, u8
-    controller_send: Sender<DroneEvent>,        // Send messages to the Sim Controller
-    controller_recv: Receiver<DroneCommand>,    // Receive messages from the Sim Controller
-    packet_recv: Receiver<Packet>,              // Receive messages from other drones
-    pdr: f32,                                   // Packet Drop Rate
-    neighbors: HashMap<NodeId, Sender<Packet>>, // Map containing the neighbors of the current drone. The key is the ID of the neighbor, the value is the channel
-    flood_requests: HashSet<(u8, u64)>,         // Contains: O(1) in average
-    crashed: bool,                              // Whether the drone is crashed
-    log_level: LogLevel,
-}
-
-impl Drone for RustafarianDrone {
-    fn new(
-        id: NodeId,
-        controller_send: Sender<DroneEvent>,
-        controller_recv: Receiver<DroneCommand>,
-        packet_recv: Receiver<Packet>,
-        packet_send: HashMap<NodeId, Sender<Packet>>,
-        pdr: f32,
-    ) -> Self {
-        let pdr = if f32::is_nan(pdr) { 0.0 } else { pdr };
-        // Saturate the pdr to 0.0..1.0
-        let pdr = pdr.clamp(0.0, 1.0);
+impl LogLevel {
+    /// Whether a message at `level` can be logged by a drone with this log level
+    pub fn allows(&self, level: LogLevel) -> bool {
+        match level {
+            // ERRORs can be logged if the drone log_level is set to anything except NONE
+            LogLevel::ERROR => *self != LogLevel::NONE,
+            // INFOs can be logged if the drone log_level is INFO or DEBUG
+            LogLevel::INFO => matches!(self, LogLevel::INFO | LogLevel::DEBUG),
+            // DEBUGs can be logged if the drone log_level is set to DEBUG
+            LogLevel::DEBUG => *self == LogLevel::DEBUG,
+            LogLevel::NONE => false,
+        }
+    }
 
-        let mut log_level: LogLevel;
+    /// Read the log level from the `RUSTAFARIAN_LOG_LEVEL` environment variable.
+    /// If the variable is missing or contains an invalid value, logging is disabled.
+    pub fn from_env() -> LogLevel {
         match env::var(LOG_ENV_VAR) {
             Ok(val) => match val.as_str() {
-                "NONE" => {
-                    log_level = LogLevel::NONE;
-                }
-                "ERROR" => {
-                    log_level = LogLevel::ERROR;
-                }
-                "INFO" => {
-                    log_level = LogLevel::INFO;
-                }
-                "DEBUG" => {
-                    log_level = LogLevel::DEBUG;
-                }
+                "NONE" => LogLevel::NONE,
+                "ERROR" => LogLevel::ERROR,
+                "INFO" => LogLevel::INFO,
+                "DEBUG" => LogLevel::DEBUG,
                 _ => {
                     println!(
                         "Wrong value [{}] in environment variable {},\
                             logger will be disabled by default",
                         val, LOG_ENV_VAR
                     );
-                    log_level = LogLevel::NONE;
+                    LogLevel::NONE
                 }
             },
             Err(e) => {
@@ -85,502 +92,470 @@ impl Drone for RustafarianDrone {
                      logger will be disabled by default",
                     LOG_ENV_VAR
                 );
-                log_level = LogLevel::NONE;
+                LogLevel::NONE
             }
         }
+    }
+}
 
-        Self {
+/// Derive the seed of a single drone from a seed shared by the whole network, so that drones with
+/// different IDs don't drop the same packets, while the same network seed always gives the same
+/// result.
+///
+/// ### Arguments
+///
+/// * `global_seed: u64` - The seed shared by the whole network
+/// * `id: NodeId` - The ID of the drone
+pub fn drone_seed(global_seed: u64, id: NodeId) -> u64 {
+    // SplitMix64 finalizer, spreads the ID over all the bits of the seed
+    let mut z = global_seed ^ (id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Read the network seed from the `RUSTAFARIAN_SEED` environment variable.
+/// Returns `None` if the variable is missing or is not a valid `u64`.
+fn global_seed_from_env() -> Option<u64> {
+    let val = env::var(SEED_ENV_VAR).ok()?;
+    match val.parse() {
+        Ok(seed) => Some(seed),
+        Err(_) => {
+            println!(
+                "Wrong value [{}] in environment variable {}, \
+                    the seed will be taken from the system entropy",
+                val, SEED_ENV_VAR
+            );
+            None
+        }
+    }
+}
+
+/// Name of the type of a packet, used in logs and statistics.
+pub fn packet_type_name(pack_type: &PacketType) -> &'static str {
+    match pack_type {
+        PacketType::MsgFragment(_) => "MsgFragment",
+        PacketType::Ack(_) => "Ack",
+        PacketType::Nack(_) => "Nack",
+        PacketType::FloodRequest(_) => "FloodRequest",
+        PacketType::FloodResponse(_) => "FloodResponse",
+    }
+}
+
+/// Saturate a Packet Drop Rate to `0.0..1.0`, treating `NaN` as `0.0`.
+fn saturate_pdr(pdr: f32) -> f32 {
+    let pdr = if f32::is_nan(pdr) { 0.0 } else { pdr };
+    pdr.clamp(0.0, 1.0)
+}
+
+/// The drone running on crossbeam channels: a thin runtime around the [`DroneCore`], which owns
+/// the channels, delivers the inputs to the core and carries out the actions it returns.
+pub struct RustafarianDrone {
+    core: DroneCore,                         // The logic of the drone, without channels
+    controller_send: Sender<DroneEvent>,     // Send messages to the Sim Controller
+    controller_recv: Receiver<DroneCommand>, // Receive messages from the Sim Controller
+    packet_recv: Receiver<Packet>,           // Receive messages from other drones
+    neighbors: HashMap<NodeId, Sender<Packet>>, // Map containing the neighbors of the current drone. The key is the ID of the neighbor, the value is the channel
+    stats: StatsHandle,                         // Counters of the traffic handled by the drone
+    // Receive the commands that are not part of the wg protocol
+    extension_recv: Receiver<RustafarianCommand>,
+    // Packets waiting to be handled in priority mode, `None` if packets are handled in arrival order
+    inbox: Option<PriorityInbox>,
+    // What to do when the channel of a neighbor is full, and how long to wait for room before
+    full_queue_policy: FullQueuePolicy,
+    send_timeout: Option<Duration>,
+    // Packets held for the neighbors with a full channel, with `FullQueuePolicy::Overflow`
+    overflow: OverflowQueues,
+    // Where the packets crossing the drone are recorded, `None` if they are not
+    capture: Option<CaptureWriter>,
+}
+
+impl Drone for RustafarianDrone {
+    fn new(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+        pdr: f32,
+    ) -> Self {
+        RustafarianDroneBuilder::new(
             id,
             controller_send,
             controller_recv,
             packet_recv,
-            neighbors: packet_send,
-            pdr,
-            flood_requests: HashSet::new(),
-            crashed: false,
-            log_level,
-        }
+            packet_send,
+        )
+        .pdr(pdr)
+        .build()
     }
 
     fn run(&mut self) {
+        // A closed channel is replaced with one that never fires, otherwise the biased select
+        // would keep returning its error and never read the other channels
+        let mut controller_open = true;
+        let mut packets_open = true;
+
         // While the drone is not crashed, listen on both channels
-        while !self.crashed {
+        while !self.core.is_crashed() {
+            let queued = self.inbox.as_ref().map_or_else(never, PriorityInbox::ready);
+            let retry = self.overflow.retry();
+            if !controller_open && !packets_open && !self.has_queued_packets() {
+                self.stop(StopReason::Disconnected);
+                return;
+            }
+
             select_biased! {
-                recv(self.controller_recv) -> command => {
-                    if let Ok(command) = command {
-                        self.handle_command(command);
+                recv(self.controller_recv) -> command => match command {
+                    Ok(command) => self.handle_command(command),
+                    Err(_) => {
+                        self.log("Controller channel closed", LogLevel::INFO);
+                        self.controller_recv = never();
+                        controller_open = false;
                     }
-                }
-                recv(self.packet_recv) -> packet => {
-                    if let Ok(packet) = packet {
-                        self.handle_packet(packet);
+                },
+                recv(self.extension_recv) -> command => match command {
+                    Ok(command) => self.handle_extension_command(command),
+                    Err(_) => self.extension_recv = never(),
+                },
+                recv(retry) -> _ => self.flush_overflows(),
+                recv(self.packet_recv) -> packet => match packet {
+                    Ok(packet) => self.receive_packet(packet),
+                    Err(_) => {
+                        self.log("Packet channel closed", LogLevel::INFO);
+                        self.packet_recv = never();
+                        packets_open = false;
                     }
                 },
+                recv(queued) -> _ => self.handle_queued_packet(),
             }
         }
 
-        // If the drone crashes, then only listen on its own receiving channel until no more
-        // packet are present in the queue
-        while let Ok(packet) = self.packet_recv.recv() {
-            self.handle_packet(packet);
+        // If the drone crashes, it keeps handling the packets in the queue while the controller
+        // removes it from the network. Fragments are NACKed, the other packets are still forwarded.
+        // Once the controller is gone no more neighbors can be removed, and the neighbors may keep
+        // the packet channel open forever: the drone stops as soon as the queue is empty
+        while self.has_queued_packets() {
+            self.handle_queued_packet();
+        }
+        loop {
+            let removed = self.neighbors.is_empty() || !controller_open;
+            if (removed && self.packet_recv.is_empty()) || !packets_open {
+                self.stop(StopReason::Crashed);
+                return;
+            }
+
+            let retry = self.overflow.retry();
+            select_biased! {
+                recv(self.controller_recv) -> command => match command {
+                    Ok(command) => self.handle_command(command),
+                    Err(_) => {
+                        self.log("Controller channel closed", LogLevel::INFO);
+                        self.controller_recv = never();
+                        controller_open = false;
+                    }
+                },
+                recv(retry) -> _ => self.flush_overflows(),
+                recv(self.packet_recv) -> packet => match packet {
+                    Ok(packet) => self.handle_packet(packet),
+                    Err(_) => packets_open = false,
+                },
+            }
         }
     }
 }
 
 impl RustafarianDrone {
-    /// Handle packets that arrive from other drones.
-    fn handle_packet(&mut self, mut packet: Packet) {
-        // Packets are cloned before the handle otherwise they get consumed by the arms execution
-        let pack_type = packet.pack_type.clone();
-        match pack_type {
-            PacketType::Nack(_nack) => self.forward_packet(packet, true, 0),
-            PacketType::Ack(_ack) => self.forward_packet(packet, true, 0),
-            PacketType::MsgFragment(_fragment) => {
-                if self.crashed {
-                    self.send_nack_fragment(
-                        packet,
-                        NackType::ErrorInRouting(self.id),
-                        _fragment.fragment_index,
-                    );
-                    return;
-                }
+    /// Handle a packet received from the packet channel. In priority mode, the packet and the ones
+    /// waiting in the channel are moved to the inbox, then the most urgent of them is handled.
+    fn receive_packet(&mut self, packet: Packet) {
+        let Some(inbox) = self.inbox.as_mut() else {
+            self.handle_packet(packet);
+            return;
+        };
 
-                self.forward_packet(packet, false, _fragment.fragment_index)
-            }
-            PacketType::FloodRequest(_flood_request) => {
-                self.handle_flood_req(_flood_request, packet.session_id, packet.routing_header);
-            }
-            PacketType::FloodResponse(_flood_response) => {
-                // If the packet is a flood response, we just forward it
-                self.forward_packet(packet, true, 0);
-            }
+        inbox.push(packet);
+        // Only the packets already waiting, a fast sender can't keep the drone draining forever
+        for packet in self.packet_recv.try_iter().take(self.packet_recv.len()) {
+            inbox.push(packet);
         }
+        let queued = inbox.len();
+        self.stats.update(|stats| stats.record_queued(queued));
+
+        self.handle_queued_packet();
+    }
+
+    /// Handle the next packet of the inbox, if any, see [`PriorityInbox`].
+    fn handle_queued_packet(&mut self) {
+        let Some((packet, turn)) = self.inbox.as_mut().and_then(PriorityInbox::pop) else {
+            return;
+        };
+        self.stats.update(|stats| stats.record_turn(turn));
+        self.handle_packet(packet);
+    }
+
+    /// Whether packets are waiting in the inbox, always `false` outside of priority mode
+    fn has_queued_packets(&self) -> bool {
+        self.inbox.as_ref().is_some_and(|inbox| !inbox.is_empty())
+    }
+
+    /// Handle packets that arrive from other drones.
+    fn handle_packet(&mut self, packet: Packet) {
+        self.capture_packet(Direction::Ingress, previous_hop(&packet), &packet);
+        let actions = self.core.on_packet(packet);
+        self.execute(actions);
     }
 
     /// Handle commands from the Simulation Controller.
+    /// Once the drone is crashed, only `RemoveSender` is handled and the other commands are ignored.
     fn handle_command(&mut self, command: DroneCommand) {
-        match command {
-            DroneCommand::AddSender(node_id, sender) => self.add_neighbor(node_id, sender),
-            DroneCommand::SetPacketDropRate(pdr) => self.set_packet_drop_rate(pdr),
+        let actions = match command {
+            DroneCommand::AddSender(node_id, sender) => {
+                // The core ignores the command once crashed, the channel is ignored as well
+                if !self.core.is_crashed() {
+                    self.neighbors.insert(node_id, sender.clone());
+                }
+                self.core
+                    .on_command(DroneCommand::AddSender(node_id, sender))
+            }
             DroneCommand::RemoveSender(node_id) => self.remove_sender(node_id),
-            DroneCommand::Crash => self.make_crash(),
-        }
+            command => self.core.on_command(command),
+        };
+        self.execute(actions);
     }
 
-    /// Add a neighbor to the list, can only be called by the Simulation Controller.
-    ///
-    /// ### Arguments
-    ///
-    /// * `node_id: u8` - The ID for the new node
-    /// * `neighbor: Sender<Packet>` - The sender channel for the new node
-    fn add_neighbor(&mut self, node_id: u8, neighbor: Sender<Packet>) {
-        self.neighbors.insert(node_id, neighbor);
+    /// Handle commands that are not part of the `DroneCommand` protocol.
+    fn handle_extension_command(&mut self, command: RustafarianCommand) {
+        let actions = self.core.on_extension_command(command);
+        self.execute(actions);
     }
 
     /// Remove a node from the neighbors using the ID. Can only be called by the Simulation Controller.
-    fn remove_sender(&mut self, node_id: u8) {
+    /// The packets held for the node can't be delivered anymore.
+    fn remove_sender(&mut self, node_id: u8) -> Vec<Action> {
         self.neighbors.remove(&node_id);
+        let mut actions = self.core.on_command(DroneCommand::RemoveSender(node_id));
+        for packet in self.overflow.remove(node_id) {
+            actions.extend(
+                self.core
+                    .on_send_failed(packet, node_id, SendFailure::Disconnected),
+            );
+        }
+        actions
+    }
+
+    /// Set the drop rate of the link towards a neighbor, see [`DroneCore::set_link_pdr`].
+    pub fn set_link_pdr(&mut self, node_id: NodeId, pdr: f32) {
+        self.core.set_link_pdr(node_id, pdr);
     }
 
-    /// Set the status of the drone as crashed. Can only be called by the Simulation Controller.
-    fn make_crash(&mut self) {
-        self.crashed = true;
+    /// Set the drop policy of the link towards a neighbor, overriding the drone policy on that link.
+    pub fn set_link_drop_policy<P: DropPolicy + 'static>(&mut self, node_id: NodeId, policy: P) {
+        self.core.set_link_drop_policy(node_id, policy);
     }
 
-    /// Change the packet drop rate. Can only be called by the Simulation Controller.
-    fn set_packet_drop_rate(&mut self, pdr: f32) {
-        self.pdr = pdr;
+    /// Remove the drop policy of the link towards a neighbor, so that the drone policy is used.
+    pub fn clear_link_pdr(&mut self, node_id: NodeId) {
+        self.core.clear_link_pdr(node_id);
     }
 
-    /// Check whether the packet should be dropped, using a random number and the Packet Drop Rate.
-    /// ---
-    /// ### Returns
-    /// `true` if the packet should be dropped, `false` otherwise
-    fn should_drop(&self) -> bool {
-        return rand::thread_rng().gen_range(0.0..1.0) < self.pdr;
+    /// Handles a flood request packet, see [`DroneCore::on_packet`].
+    pub fn handle_flood_req(
+        &mut self,
+        packet: FloodRequest,
+        session_id: u64,
+        routing_header: SourceRoutingHeader,
+    ) {
+        self.core
+            .handle_flood_req(packet, session_id, routing_header);
+        let actions = self.core.take_actions();
+        self.execute(actions);
     }
 
-    /// Forwards a packet to the next node, doing checks such as:
-    /// 1. The current drone is the intended receiver
-    /// 2. Check that the drone is not the last hop
+    /// Carry out the actions returned by the core, in order.
     ///
     /// ### Arguments
     ///
-    /// * `packet: Packet` - The packet to forward
-    /// * `skip_pdr_check: bool` - If it should skip the check for the Packet Drop Rate before sending.
-    /// True for ACKs, NACKs, Flood messages
-    /// * `fragment_index: u64` - Id of the fragment to forward
-    fn forward_packet(&mut self, mut packet: Packet, skip_pdr_check: bool, fragment_index: u64) {
-        // Step 1: check I'm the intended receiver
-        let curr_hop = packet.routing_header.hops[packet.routing_header.hop_index];
-        if self.id != curr_hop {
-            // Error, I'm not the one who's supposed to receive this
-            self.send_nack_fragment(
-                packet,
-                NackType::UnexpectedRecipient(self.id),
-                fragment_index,
-            );
-            return;
-        }
-
-        let mut new_packet = packet.clone();
-        // Step 2: increase the hop index
-        new_packet.routing_header.hop_index += 1;
-
-        let next_hop_index = new_packet.routing_header.hop_index;
-
-        // Step 3: check I'm not the last hop
-        if next_hop_index >= new_packet.routing_header.hops.len() {
-            // Error, I'm the last hop!
-            self.send_nack_fragment(packet, NackType::DestinationIsDrone, fragment_index);
-            return;
+    /// * `actions: Vec<Action>` - The actions to carry out
+    fn execute(&mut self, actions: Vec<Action>) {
+        for action in actions {
+            match action {
+                Action::Send { to, packet } => self.egress(packet, to),
+                Action::Event(event) => {
+                    self.controller_send.send(event);
+                }
+                Action::Shortcut(packet) => {
+                    self.controller_send
+                        .send(DroneEvent::ControllerShortcut(packet));
+                }
+            }
         }
-
-        // Skip_pdr_check: true only for ACK and NACK, so in those two cases we don't send the ACK back to the sender.
-        self.send_packet(new_packet, skip_pdr_check, fragment_index);
-        // TODO: send PacketSent event to the controller
     }
 
-    /// Send a packet to the target's channel. The target is the next node in the routing header.
+    /// Send a packet to a neighbor, reporting the outcome to the core:
+    /// - When the packet is sent, the core notifies the Simulation Controller
+    /// - When the neighbor can't be reached, the core handles the packet as undeliverable
+    /// - When the channel of the neighbor is full, the packet is handled according to the
+    ///   [`FullQueuePolicy`]
     ///
     /// ### Arguments
     ///
-    /// * `mut packet: Packet` - The packet to send
-    /// * `skip_pdr_check: bool` - If it should skip the check for the Packet Drop Rate before sending.
-    /// True for ACKs, NACKs, Flood messages
-    /// * `fragment_index: u64` - Id of the fragment to forward
-    ///
-    /// ---
-    /// ### Returns
-    /// `true` if the packet was sent successfully, `false` otherwise.
-    fn send_packet(
-        &mut self,
-        mut packet: Packet,
-        skip_pdr_check: bool,
-        fragment_index: u64,
-    ) -> bool {
-        let mut result = false;
-
-        let next_hop_index = packet.routing_header.hop_index;
-
-        // Check if the next_hop_index is valid
-        if next_hop_index >= packet.routing_header.hops.len() {
-            self.log(
-                format!(
-                    "Error: next_hop_index ({}) >= packet.routing_header.hops.len() ({})",
-                    next_hop_index,
-                    packet.routing_header.hops.len()
-                )
-                .as_str(),
-                LogLevel::ERROR,
-            );
+    /// * `packet: Packet` - The packet to send
+    /// * `next_hop: NodeId` - The neighbor the packet is sent to
+    fn egress(&mut self, packet: Packet, next_hop: NodeId) {
+        let Some(channel) = self.neighbors.get(&next_hop) else {
+            self.send_failed(packet, next_hop, SendFailure::Disconnected);
+            return;
+        };
 
-            // println!(
-            //     "Error: next_hop_index ({}) >= packet.routing_header.hops.len() ({})",
-            //     next_hop_index, packet.routing_header.hops.len()
-            // );
-            return false;
+        // The packets already held for the neighbor are sent first, keeping the order
+        if self.overflow.is_waiting(next_hop) {
+            self.stats.update(|stats| stats.record_congestion(next_hop));
+            self.handle_full_queue(packet, next_hop);
+            self.flush_overflow(next_hop);
+            return;
         }
 
-        // Check I have the next hop as neighbor
-        let next_hop = packet.routing_header.hops[next_hop_index];
-
-        match self.neighbors.get(&next_hop) {
-            Some(channel) => {
-                // Check if packet can be dropped, if so check the PDR
-                if !skip_pdr_check && self.should_drop() {
-                    // Notify controller that a packet has been dropped
-                    self.controller_send
-                        .send(DroneEvent::PacketDropped(packet.clone()));
-
-                    // Packet dropped
-                    self.send_nack_fragment(packet, NackType::Dropped, fragment_index);
-
-                    return false;
-                }
-
-                // Clone needed because we then needs to send it as part of the PacketSent event
-                match channel.send(packet.clone()) {
-                    Ok(()) => {
-                        // Notify controller that a packet has been correctly sent
-                        self.controller_send.send(DroneEvent::PacketSent(packet));
-                        result = true;
-                    }
-                    Err(error) => {
-                        // Should never reach this error, SC should prevent it
-                        self.log(
-                            "Error while sending packet on closed channel",
-                            LogLevel::ERROR,
-                        );
-
-                        // println!("Error while sending packet on closed channel");
-
-                        // If true, it means packet is an ACK/NACK/FLOOD_RESP, so it should be
-                        // routed through the SC in order to reach it's destination
-                        if skip_pdr_check {
-                            self.controller_send
-                                .send(DroneEvent::ControllerShortcut(packet.clone()));
+        // The only copy on the forwarding path: the PacketSent event needs its own packet
+        let event_packet = packet.clone();
+        let result = match (self.full_queue_policy, self.send_timeout) {
+            (FullQueuePolicy::Block, _) => channel
+                .send(packet)
+                .map_err(|SendError(packet)| TrySendError::Disconnected(packet)),
+            (_, Some(timeout)) => {
+                channel
+                    .send_timeout(packet, timeout)
+                    .map_err(|error| match error {
+                        SendTimeoutError::Timeout(packet) => TrySendError::Full(packet),
+                        SendTimeoutError::Disconnected(packet) => {
+                            TrySendError::Disconnected(packet)
                         }
-
-                        self.send_nack_fragment(
-                            packet,
-                            NackType::ErrorInRouting(next_hop),
-                            fragment_index,
-                        );
-                    }
-                }
+                    })
             }
-            None => {
-                self.log(
-                    format!(
-                        "Error: next_hop ({}) is not a neighbor of drone {}",
-                        next_hop, self.id
-                    )
-                    .as_str(),
-                    LogLevel::ERROR,
-                );
+            (_, None) => channel.try_send(packet),
+        };
 
-                // println!(
-                //     "Error: next_hop ({}) is not a neighbor of drone {}",
-                //     next_hop, self.id
-                // );
-                // Next hop is not my neighbour
-                self.send_nack_fragment(packet, NackType::ErrorInRouting(next_hop), fragment_index);
+        match result {
+            Ok(()) => self.sent(event_packet, next_hop),
+            Err(TrySendError::Full(packet)) => {
+                self.stats.update(|stats| stats.record_congestion(next_hop));
+                self.handle_full_queue(packet, next_hop);
+            }
+            // The packet is given back by the channel
+            Err(TrySendError::Disconnected(packet)) => {
+                self.send_failed(packet, next_hop, SendFailure::Disconnected);
             }
         }
+    }
+
+    /// Report to the core that a packet has been sent, see [`DroneCore::on_sent`].
+    fn sent(&mut self, packet: Packet, next_hop: NodeId) {
+        self.capture_packet(Direction::Egress, Some(next_hop), &packet);
+        let actions = self.core.on_sent(next_hop, packet);
+        self.execute(actions);
+    }
 
-        result
+    /// Report to the core that a packet couldn't be sent, see [`DroneCore::on_send_failed`].
+    fn send_failed(&mut self, packet: Packet, next_hop: NodeId, failure: SendFailure) {
+        let actions = self.core.on_send_failed(packet, next_hop, failure);
+        self.execute(actions);
     }
 
-    /// Send a NACK packet to the previous node. The target is taken by reversing
-    /// the routing header, starting from the current hop.
+    /// Handle a packet that doesn't fit in the channel of a neighbor: with
+    /// [`FullQueuePolicy::Overflow`] it's held until the channel has room, otherwise it's dropped.
     ///
     /// ### Arguments
     ///
-    /// * `mut packet: Packet` - The packet that couldn't be sent
-    /// * `nack_type: NackType` - The cause for the loss of the packet (Drop, ErrorInRouting, ...)
-    /// * `fragment_index: u64` - Id of the packet that couldn't be sent
-    fn send_nack_fragment(&mut self, mut packet: Packet, nack_type: NackType, fragment_index: u64) {
-        // Get index for the current node
-
-        let nack = wg_2024::packet::Nack {
-            fragment_index,
-            nack_type,
+    /// * `packet: Packet` - The packet, with the hop index pointing to the next hop
+    /// * `next_hop: NodeId` - The neighbor with the full channel
+    fn handle_full_queue(&mut self, packet: Packet, next_hop: NodeId) {
+        let packet = match self.full_queue_policy {
+            FullQueuePolicy::Overflow { capacity } => {
+                match self.overflow.push(next_hop, packet, capacity) {
+                    None => {
+                        let held = self.overflow.len(next_hop);
+                        self.stats.update(|stats| stats.record_overflow(held));
+                        return;
+                    }
+                    Some(packet) => packet,
+                }
+            }
+            FullQueuePolicy::Block | FullQueuePolicy::Drop => packet,
         };
 
-        if !self.send_back(&mut packet, PacketType::Nack(nack)) {
-            // Nack can't be forwarded, send it to SC
-            self.controller_send
-                .send(DroneEvent::ControllerShortcut(packet));
-        }
+        self.send_failed(packet, next_hop, SendFailure::Full);
     }
 
-    /**
-     * Calls send_nack_fragment(packet, nack_type, 0)
-     * Use in case the original packet was not a fragment
-     */
-    // fn send_nack(&mut self, packet: Packet, nack_type: NackType) {
-    //     self.send_nack_fragment(packet, nack_type, 0);
-    // }
-
-    /// Handles a flood request packet, follows these steps:
-    /// 1. The drone adds itself to the path_trace
-    /// 2. If the ID is in the memory: create and send a FloodResponse
-    /// 3. Otherwise:
-    ///     - If has neighbors forwards the packet to its neighbors
-    ///     - If no neighbors send it to node from which it received it
-    pub fn handle_flood_req(
-        &mut self,
-        mut packet: FloodRequest,
-        session_id: u64,
-        mut routing_header: SourceRoutingHeader,
-    ) {
-        // If we have already have the flood id from that sender
-        // Request already handled, prepare response
-        let flood_from_sender = self
-            .flood_requests
-            .get(&(packet.initiator_id, packet.flood_id));
-        if flood_from_sender.is_some() && !self.crashed {
-            // Get the ID of the drone that sent the request
-            let sender_id = packet.path_trace.last().unwrap().0;
-            // Add myself to the path trace
-            packet.path_trace.push((self.id, NodeType::Drone));
-
-            let mut route: Vec<u8> = packet.path_trace.iter().map(|node| node.0).collect();
-            route.reverse();
-
-            let response = FloodResponse {
-                flood_id: packet.flood_id,
-                path_trace: packet.path_trace,
-            };
-
-            // Create the packet with the route provided in the path trace
-            let new_packet = Packet {
-                pack_type: PacketType::FloodResponse(response),
-                session_id,
-                routing_header: SourceRoutingHeader {
-                    hop_index: 1,
-                    hops: route,
-                },
+    /// Send the packets held for a neighbor, until its channel is full again.
+    fn flush_overflow(&mut self, neighbor: NodeId) {
+        while let Some(packet) = self.overflow.pop(neighbor) {
+            let Some(channel) = self.neighbors.get(&neighbor) else {
+                self.send_failed(packet, neighbor, SendFailure::Disconnected);
+                continue;
             };
 
-            let copy_for_sc = new_packet.clone();
-
-            match self.neighbors.get(&sender_id) {
-                Some(channel) => match channel.send(new_packet) {
-                    Ok(()) => {
-                        self.log(
-                            format!("Sent response to {:?}", sender_id).as_str(),
-                            LogLevel::INFO,
-                        );
-
-                        // println!("Sent response to {:?}", sender_id);
-                    }
-                    Err(error) => {
-                        // No message sent to SC. Crashed neighbours should not be in the topology
-                        self.log(
-                            "Couldn't send response, as the neighbor has crashed",
-                            LogLevel::INFO,
-                        );
-
-                        // println!("Couldn't send response, as the neighbor has crashed")
-                    }
-                },
-                _ => {}
-            }
-        } else {
-            // Send to neighbors
-            // Save the last node's ID, we don't want to send the request to it
-            let last_node = (packet.path_trace.last().unwrap().0);
-
-            // Only put myself in the path trace if I'm not crashed
-            if !self.crashed {
-                // Add our ID to the trace
-                packet.path_trace.push((self.id, NodeType::Drone));
-            }
-
-            self.flood_requests.insert((packet.initiator_id, packet.flood_id));
-
-            // Send to all neighbors
-            for neighbor in &self.neighbors {
-                let neighbor_id = neighbor.0;
-                let neighbor_channel = neighbor.1;
-
-                // Avoid sending to the node that send the request to us
-                if neighbor_id == &last_node {
-                    continue;
+            let event_packet = packet.clone();
+            match channel.try_send(packet) {
+                Ok(()) => self.sent(event_packet, neighbor),
+                Err(TrySendError::Full(packet)) => {
+                    self.overflow.push_front(neighbor, packet);
+                    return;
                 }
-
-                // The two clones here are required, since we are sending the same stuff to multiple nodes!
-                match neighbor_channel.send(Packet {
-                    pack_type: PacketType::FloodRequest(packet.clone()),
-                    routing_header: routing_header.clone(),
-                    session_id,
-                }) {
-                    Ok(()) => {}
-                    // No message sent to SC. Crashed neighbours should not be in the topology
-                    Err(error) => {
-                        self.log(
-                            "Couldn't send response, as the neighbor has crashed",
-                            LogLevel::INFO,
-                        );
-                        // println!("Couldn't send response, as the neighbor has crashed")
-                    }
+                Err(TrySendError::Disconnected(packet)) => {
+                    self.send_failed(packet, neighbor, SendFailure::Disconnected);
                 }
             }
         }
     }
 
-    /// Send a packet back to the previous node.The target is taken by reversing the routing header,
-    /// starting from the current hop.
+    /// Send the packets held for every neighbor, as far as their channels have room.
+    fn flush_overflows(&mut self) {
+        for neighbor in self.overflow.neighbors() {
+            self.flush_overflow(neighbor);
+        }
+    }
+
+    /// Record a packet crossing the drone, if the capture is enabled. A capture that can't be
+    /// written is logged, the packet is handled anyway.
     ///
     /// ### Arguments
     ///
-    /// * `acked_packet: &mut Packet` - The packet that couldn't be sent
-    /// * `acknowledgment: PacketType` - The type of acknowledgment to send back
-    /// ---
-    /// ### Returns
-    /// `true` if the packet was sent back correctly, `false` otherwise.
-    fn send_back(&mut self, acked_packet: &mut Packet, acknowledgment: PacketType) -> bool {
-        let route = self.reverse_route(&acked_packet.routing_header);
-
-        let routing_header = SourceRoutingHeader {
-            hop_index: 1,
-            hops: route,
+    /// * `direction: Direction` - Whether the packet was received or sent
+    /// * `neighbor: Option<NodeId>` - The neighbor the packet came from or was sent to
+    /// * `packet: &Packet` - The packet
+    fn capture_packet(&mut self, direction: Direction, neighbor: Option<NodeId>, packet: &Packet) {
+        let Some(capture) = self.capture.as_mut() else {
+            return;
         };
 
-        let new_packet = Packet {
-            pack_type: acknowledgment,
-            session_id: acked_packet.session_id,
-            routing_header,
-        };
+        let record = CaptureRecord::new(self.core.id(), direction, neighbor, packet.clone());
+        if let Err(error) = capture.write(&record) {
+            self.core.log_packet(
+                format!("Couldn't write the capture: {}", error).as_str(),
+                LogLevel::ERROR,
+                packet,
+            );
+        }
+    }
 
-        self.send_packet(new_packet, true, 0)
+    /// Record why the drone stopped running, in the logs and in the statistics.
+    fn stop(&self, reason: StopReason) {
+        self.log(format!("Stopped: {:?}", reason).as_str(), LogLevel::INFO);
+        self.stats.update(|stats| stats.stopped = Some(reason));
     }
 
-    /// Reverse the route contained in the SourceRoutingHeader, starting from the current hop.
-    fn reverse_route(&self, header: &SourceRoutingHeader) -> Vec<u8> {
-        if let Some(self_index) = header.hops.iter().position(|id| id == &self.id) {
-            let mut route: Vec<u8> = header.hops.clone();
-            route.truncate(self_index + 1);
-            route.reverse();
-            route
-        } else {
-            vec![]
-        }
+    /// Shared handle to the statistics of the drone, which can be read from another thread.
+    pub fn stats(&self) -> StatsHandle {
+        self.stats.clone()
     }
 
-    /// Utility method used to cleanly log information, differentiating on three different levels
-    ///
-    /// # Args
-    /// * `log_message: &str` - the message to log
-    /// * `level: u8` - the level of the log
+    /// Log a message with the logger of the drone, see [`DroneCore::log`].
     pub fn log(&self, log_message: &str, level: LogLevel) {
-        match level {
-            LogLevel::ERROR => {
-                // Handle a log at ERROR level
-                match self.log_level {
-                    LogLevel::NONE => {}
-
-                    // ERRORs can be logged if the drone log_level is set to anything except NONE
-                    _ => {
-                        eprintln!(
-                            "[Rustafarian Drone {}] - LEVEL: ERROR >>> {}",
-                            self.id, log_message
-                        );
-                    }
-                }
-            }
-            LogLevel::INFO => {
-                // Handle a log at INFO level
-                match self.log_level {
-                    LogLevel::NONE => {}
-                    LogLevel::ERROR => {}
-
-                    // INFOs can be logged if the drone log_level is INFO or DEBUG
-                    _ => {
-                        println!(
-                            "[Rustafarian Drone {}] - LEVEL: INFO >>> {}",
-                            self.id, log_message
-                        );
-                    }
-                }
-            }
-            LogLevel::DEBUG => {
-                // Handle a log at DEBUG level
-                match self.log_level {
-                    // DEBUGs can be logged if the drone log_level is set to DEBUG
-                    LogLevel::DEBUG => {
-                        println!(
-                            "[Rustafarian Drone {}] - LEVEL: DEBUG >>> {}",
-                            self.id, log_message
-                        );
-                    }
-                    _ => {}
-                }
-            }
+        self.core.log(log_message, level);
+    }
 
-            LogLevel::NONE => {}
-        }
+    /// Log a message about a packet with the logger of the drone, see [`DroneCore::log_packet`].
+    pub fn log_packet(&self, log_message: &str, level: LogLevel, packet: &Packet) {
+        self.core.log_packet(log_message, level, packet);
     }
 }
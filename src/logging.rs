@@ -0,0 +1,158 @@
+use crate::LogLevel;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use wg_2024::network::NodeId;
+
+/// A single log message of a drone
+#[derive(Debug, Clone)]
+pub struct LogRecord<'a> {
+    /// The ID of the drone writing the log
+    pub drone_id: NodeId,
+    /// The level of the log, never `NONE`
+    pub level: LogLevel,
+    /// The message to log
+    pub message: &'a str,
+    /// The type of the packet the message refers to, if any
+    pub packet_type: Option<&'static str>,
+    /// The session ID of the packet the message refers to, if any
+    pub session_id: Option<u64>,
+}
+
+impl LogRecord<'_> {
+    /// Format the record as a single line, the same way the drone prints it on the terminal
+    pub fn to_line(&self) -> String {
+        format!(
+            "[Rustafarian Drone {}] - LEVEL: {:?} >>> {}",
+            self.drone_id, self.level, self.message
+        )
+    }
+}
+
+/// Destination of the logs of a drone.
+///
+/// The drone filters the records using its log level, so a sink receives only the records
+/// that have to be written.
+pub trait LogSink: Send {
+    /// Write a log record
+    fn log(&self, record: &LogRecord);
+}
+
+/// Write errors to the standard error and everything else to the standard output.
+/// This is the default sink of the drone.
+#[derive(Debug, Clone, Default)]
+pub struct StdSink;
+
+impl LogSink for StdSink {
+    fn log(&self, record: &LogRecord) {
+        match record.level {
+            LogLevel::ERROR => eprintln!("{}", record.to_line()),
+            _ => println!("{}", record.to_line()),
+        }
+    }
+}
+
+/// Append the logs to a file, one line per record.
+#[derive(Debug)]
+pub struct FileSink {
+    file: File,
+}
+
+impl FileSink {
+    /// Open the file in append mode, creating it if it doesn't exist.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    /// Open the file `drone-<id>.log` inside `dir`, so that every drone has its own file.
+    pub fn in_dir<P: AsRef<Path>>(dir: P, drone_id: NodeId) -> io::Result<Self> {
+        Self::new(dir.as_ref().join(format!("drone-{}.log", drone_id)))
+    }
+}
+
+impl LogSink for FileSink {
+    fn log(&self, record: &LogRecord) {
+        // Errors can't be logged anywhere, so they are ignored
+        let _ = writeln!(&self.file, "{}", record.to_line());
+    }
+}
+
+/// Keep the last `capacity` log lines in memory, so that tests can inspect them.
+///
+/// Clones share the same buffer: keep a clone before giving the sink to the drone.
+#[derive(Debug, Clone)]
+pub struct MemorySink {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl MemorySink {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// The lines currently in the buffer, from the oldest to the newest
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Remove every line from the buffer
+    pub fn clear(&self) {
+        self.lines.lock().unwrap().clear();
+    }
+}
+
+impl LogSink for MemorySink {
+    fn log(&self, record: &LogRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(record.to_line());
+    }
+}
+
+/// Send the logs to `tracing`, with the drone ID, the packet type and the session ID as fields.
+/// This is the default sink when the `tracing` feature is enabled.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Default)]
+pub struct TracingSink;
+
+#[cfg(feature = "tracing")]
+impl LogSink for TracingSink {
+    fn log(&self, record: &LogRecord) {
+        macro_rules! event {
+            ($level:expr) => {
+                match (record.packet_type, record.session_id) {
+                    (Some(packet_type), Some(session_id)) => tracing::event!(
+                        $level,
+                        drone_id = record.drone_id,
+                        packet_type,
+                        session_id,
+                        "{}",
+                        record.message
+                    ),
+                    _ => tracing::event!($level, drone_id = record.drone_id, "{}", record.message),
+                }
+            };
+        }
+
+        match record.level {
+            LogLevel::ERROR => event!(tracing::Level::ERROR),
+            LogLevel::INFO => event!(tracing::Level::INFO),
+            LogLevel::DEBUG => event!(tracing::Level::DEBUG),
+            LogLevel::NONE => {}
+        }
+    }
+}
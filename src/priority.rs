@@ -0,0 +1,103 @@
+use crossbeam_channel::{never, tick, Receiver};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use wg_2024::packet::{Packet, PacketType};
+
+/// Control packets handled in a row while fragments are waiting, unless configured otherwise
+pub const DEFAULT_CONTROL_BURST: usize = 8;
+
+/// What happened when a packet was taken from the [`PriorityInbox`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Turn {
+    /// No packet of the other class was waiting
+    Alone,
+    /// A control packet was taken while fragments were waiting
+    Prioritized,
+    /// A fragment was taken while control packets were waiting, so that fragments are not starved
+    Fair,
+}
+
+/// Packets received by a drone in priority mode, waiting to be handled.
+///
+/// Control packets (everything but `MsgFragment`) are handled before the fragments, each class in
+/// arrival order. After `control_burst` control packets in a row, a waiting fragment is handled
+/// before the next control packet.
+#[derive(Debug)]
+pub(crate) struct PriorityInbox {
+    control: VecDeque<Packet>,
+    fragments: VecDeque<Packet>,
+    control_burst: usize,
+    control_in_row: usize,    // Control packets handled since the last fragment
+    ready: Receiver<Instant>, // Always ready, selected while packets are waiting
+}
+
+impl PriorityInbox {
+    pub(crate) fn new(control_burst: usize) -> Self {
+        Self {
+            control: VecDeque::new(),
+            fragments: VecDeque::new(),
+            control_burst: control_burst.max(1),
+            control_in_row: 0,
+            ready: tick(Duration::ZERO),
+        }
+    }
+
+    pub(crate) fn push(&mut self, packet: Packet) {
+        match packet.pack_type {
+            PacketType::MsgFragment(_) => self.fragments.push_back(packet),
+            _ => self.control.push_back(packet),
+        }
+    }
+
+    /// Take the next packet to handle.
+    ///
+    /// ### Returns
+    /// The packet with the reason it was chosen, `None` if no packet is waiting
+    pub(crate) fn pop(&mut self) -> Option<(Packet, Turn)> {
+        let fragment_turn = self.control_in_row >= self.control_burst;
+        match (self.control.is_empty(), self.fragments.is_empty()) {
+            (true, true) => None,
+            (false, true) => {
+                self.control_in_row += 1;
+                self.control.pop_front().map(|packet| (packet, Turn::Alone))
+            }
+            (true, false) => {
+                self.control_in_row = 0;
+                self.fragments
+                    .pop_front()
+                    .map(|packet| (packet, Turn::Alone))
+            }
+            (false, false) if fragment_turn => {
+                self.control_in_row = 0;
+                self.fragments
+                    .pop_front()
+                    .map(|packet| (packet, Turn::Fair))
+            }
+            (false, false) => {
+                self.control_in_row += 1;
+                self.control
+                    .pop_front()
+                    .map(|packet| (packet, Turn::Prioritized))
+            }
+        }
+    }
+
+    /// Number of packets waiting
+    pub(crate) fn len(&self) -> usize {
+        self.control.len() + self.fragments.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Channel that is ready whenever a packet is waiting, used to select it together with the
+    /// channels of the drone
+    pub(crate) fn ready(&self) -> Receiver<Instant> {
+        if self.is_empty() {
+            never()
+        } else {
+            self.ready.clone()
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use crate::capture::{CaptureRecord, Direction};
+use crate::RustafarianDroneBuilder;
+use crossbeam_channel::unbounded;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::thread;
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// A packet received by the recorded drone, with the packets it sent while handling it
+#[derive(Debug, Clone)]
+struct Step {
+    // `None` for the packets sent before the first packet received
+    ingress: Option<CaptureRecord>,
+    egress: Vec<(NodeId, Packet)>,
+}
+
+/// A step of the replay where the drone didn't send the packets that were recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Position of the step in the replay, starting from 0
+    pub step: usize,
+    /// The packet received by the drone, `None` for the packets recorded before the first
+    /// packet received
+    pub ingress: Option<Packet>,
+    /// The packets sent by the recorded drone, with the neighbor they were sent to
+    pub recorded: Vec<(NodeId, Packet)>,
+    /// The packets sent by the replayed drone, with the neighbor they were sent to
+    pub replayed: Vec<(NodeId, Packet)>,
+}
+
+/// Outcome of a replay
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayReport {
+    /// Packets injected into the drone
+    pub injected: usize,
+    /// Steps where the drone sent exactly the recorded packets
+    pub matched: usize,
+    /// Steps where the drone didn't send the recorded packets, in order
+    pub divergences: Vec<Divergence>,
+}
+
+impl ReplayReport {
+    /// Whether the replayed drone sent exactly the recorded packets
+    pub fn is_exact(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Replay of a packet capture into a fresh [`RustafarianDrone`](crate::RustafarianDrone).
+///
+/// The capture is split in steps: every packet received by the recorded drone, with the packets
+/// it sent before receiving the next one. The received packets are injected into the new drone
+/// in the recorded order, and after each one the packets sent by the drone are compared with the
+/// recorded ones. Only the order of the packets sent to the same neighbor is compared.
+///
+/// The capture doesn't contain the settings of the drone and the commands of the Simulation
+/// Controller: the drone is configured through [`Replay::run_with`], and a drone that was crashed
+/// or whose neighbors changed while recording diverges from that point.
+///
+/// ### Example
+///
+/// ```no_run
+/// use rustafarian_drone::capture::read_capture;
+/// use rustafarian_drone::replay::Replay;
+///
+/// let records = read_capture("captures/capture-11.jsonl").unwrap();
+/// let report = Replay::new(11, records).run_with(|builder| builder.pdr(0.1).seed(42));
+/// for divergence in &report.divergences {
+///     println!("{:?}", divergence);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Replay {
+    drone: NodeId,
+    steps: Vec<Step>,
+    neighbors: BTreeSet<NodeId>,
+    speed: Option<f64>, // Speed factor of the original timing, `None` to replay without waiting
+}
+
+impl Replay {
+    /// Prepare the replay of the packets captured by a drone.
+    ///
+    /// ### Arguments
+    ///
+    /// * `drone: NodeId` - The ID of the recorded drone, the records of the other drones are
+    ///   ignored, so that the captures of a whole network can be merged
+    /// * `records: impl IntoIterator<Item = CaptureRecord>` - The records, in the order they were
+    ///   written
+    pub fn new(drone: NodeId, records: impl IntoIterator<Item = CaptureRecord>) -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        let mut neighbors = BTreeSet::new();
+
+        for record in records.into_iter().filter(|record| record.drone == drone) {
+            neighbors.extend(record.neighbor);
+            match record.direction {
+                Direction::Ingress => steps.push(Step {
+                    ingress: Some(record),
+                    egress: Vec::new(),
+                }),
+                Direction::Egress => {
+                    // Sent packets are always recorded with their neighbor
+                    let Some(neighbor) = record.neighbor else {
+                        continue;
+                    };
+                    if steps.is_empty() {
+                        steps.push(Step {
+                            ingress: None,
+                            egress: Vec::new(),
+                        });
+                    }
+                    if let Some(step) = steps.last_mut() {
+                        step.egress.push((neighbor, record.packet));
+                    }
+                }
+            }
+        }
+
+        // The replayed packets are collected one neighbor at a time
+        for step in &mut steps {
+            step.egress.sort_by_key(|(neighbor, _)| *neighbor);
+        }
+
+        Self {
+            drone,
+            steps,
+            neighbors,
+            speed: None,
+        }
+    }
+
+    /// Add a neighbor to the drone. The neighbors found in the capture are always added, this is
+    /// only needed for neighbors the recorded drone never exchanged packets with.
+    pub fn neighbor(mut self, id: NodeId) -> Self {
+        self.neighbors.insert(id);
+        self
+    }
+
+    /// Inject the packets with the recorded timing, instead of as fast as possible. With a
+    /// `speed` of 2.0 the waits between the packets are halved.
+    pub fn original_timing(mut self, speed: f64) -> Self {
+        assert!(speed > 0.0, "the replay speed must be positive");
+        self.speed = Some(speed);
+        self
+    }
+
+    /// The ID of the recorded drone
+    pub fn drone(&self) -> NodeId {
+        self.drone
+    }
+
+    /// The neighbors of the replayed drone
+    pub fn neighbors(&self) -> &BTreeSet<NodeId> {
+        &self.neighbors
+    }
+
+    /// Number of packets received by the recorded drone
+    pub fn len(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| step.ingress.is_some())
+            .count()
+    }
+
+    /// Whether the recorded drone didn't receive any packet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Replay the capture into a drone with the default settings.
+    pub fn run(&self) -> ReplayReport {
+        self.run_with(|builder| builder)
+    }
+
+    /// Replay the capture into a drone created by the builder passed to `configure`, so that the
+    /// settings of the recorded drone can be applied. To drop the same fragments, the drone needs
+    /// the same PDR, drop policy and seed as the recorded one.
+    ///
+    /// ### Arguments
+    ///
+    /// * `configure: F` - Function applying the settings to the builder of the drone
+    /// ---
+    /// ### Returns
+    /// The outcome of the replay, with the steps where the drone diverged from the capture
+    pub fn run_with<F>(&self, configure: F) -> ReplayReport
+    where
+        F: FnOnce(RustafarianDroneBuilder) -> RustafarianDroneBuilder,
+    {
+        let (controller_send, _controller_recv) = unbounded();
+        let mut packet_send = HashMap::new();
+        let mut packet_recv = BTreeMap::new();
+        for neighbor in &self.neighbors {
+            let (send, recv) = unbounded();
+            packet_send.insert(*neighbor, send);
+            packet_recv.insert(*neighbor, recv);
+        }
+        let builder = RustafarianDroneBuilder::new(
+            self.drone,
+            controller_send,
+            unbounded().1,
+            unbounded().1,
+            packet_send,
+        );
+        let mut drone = configure(builder).build();
+
+        let start = Instant::now();
+        let first_timestamp = self
+            .steps
+            .iter()
+            .find_map(|step| step.ingress.as_ref())
+            .map(|record| record.timestamp_us);
+        let mut report = ReplayReport::default();
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let mut replayed = Vec::new();
+            if let Some(record) = &step.ingress {
+                if let (Some(speed), Some(first_timestamp)) = (self.speed, first_timestamp) {
+                    let offset = record.timestamp_us.saturating_sub(first_timestamp);
+                    wait_until(start + Duration::from_micros(offset).div_f64(speed));
+                }
+                drone.handle_packet(record.packet.clone());
+                report.injected += 1;
+
+                for (neighbor, recv) in &packet_recv {
+                    replayed.extend(recv.try_iter().map(|packet| (*neighbor, packet)));
+                }
+            }
+
+            if replayed == step.egress {
+                report.matched += 1;
+            } else {
+                report.divergences.push(Divergence {
+                    step: index,
+                    ingress: step.ingress.as_ref().map(|record| record.packet.clone()),
+                    recorded: step.egress.clone(),
+                    replayed,
+                });
+            }
+        }
+        report
+    }
+}
+
+fn wait_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now {
+        thread::sleep(deadline - now);
+    }
+}
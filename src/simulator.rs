@@ -0,0 +1,443 @@
+use crate::drone_core::{Action, DroneCore, SendFailure};
+use crate::stats::DroneStats;
+use crate::RustafarianDroneBuilder;
+use crossbeam_channel::unbounded;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
+use std::mem;
+use std::time::{Duration, Instant};
+use wg_2024::config::Config;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Latency of the links whose latency is not set
+pub const DEFAULT_LATENCY: Duration = Duration::from_millis(1);
+
+/// Something that happened during the simulation, recorded in the timeline.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimEvent {
+    /// A drone sent an event to the Simulation Controller. Contains: (drone, event)
+    Drone(NodeId, DroneEvent),
+    /// A packet reached a client or a server. Contains: (client or server, packet)
+    Delivered(NodeId, Packet),
+}
+
+/// Input handled by a node at a given virtual time
+enum Input {
+    Packet(NodeId, Packet),
+    Command(NodeId, DroneCommand),
+}
+
+/// An input waiting in the queue of the simulator. Inputs are handled by time, and inputs with
+/// the same time in the order they were scheduled.
+struct Scheduled {
+    time: Duration,
+    seq: u64,
+    input: Input,
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        (self.time, self.seq) == (other.time, other.seq)
+    }
+}
+
+impl Eq for Scheduled {}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.time, self.seq).cmp(&(other.time, other.seq))
+    }
+}
+
+/// Discrete-event simulation of a network of drones, running on the current thread with a
+/// virtual clock.
+///
+/// Every drone is a [`DroneCore`]: instead of threads and channels, the packets sent by the
+/// drones are scheduled to reach their neighbor after the latency of the link, and the virtual
+/// time jumps from one input to the next. Clients and servers are passive, the packets they
+/// receive are recorded in the [`timeline`](Simulator::timeline), together with the events sent
+/// by the drones. With the same seed and the same inputs, two simulations produce the same
+/// timeline.
+///
+/// ### Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use wg_2024::network::SourceRoutingHeader;
+/// # use wg_2024::packet::{Fragment, Packet, PacketType};
+/// use rustafarian_drone::initializer::load_config;
+/// use rustafarian_drone::simulator::Simulator;
+///
+/// # let fragment = Packet {
+/// #     pack_type: PacketType::MsgFragment(Fragment {
+/// #         fragment_index: 0,
+/// #         total_n_fragments: 1,
+/// #         length: 128,
+/// #         data: [0; 128],
+/// #     }),
+/// #     routing_header: SourceRoutingHeader { hop_index: 1, hops: vec![11, 1, 2, 3, 21] },
+/// #     session_id: 1,
+/// # };
+/// // Client 11 - Drones 1, 2, 3 - Server 21
+/// let config = load_config("config/sample.toml").unwrap();
+/// let mut simulator = Simulator::from_config(&config, 42);
+/// simulator.set_latency(1, 2, Duration::from_millis(20));
+/// simulator.inject(fragment);
+/// simulator.run();
+/// println!("{:?}", simulator.timeline());
+/// ```
+pub struct Simulator {
+    drones: BTreeMap<NodeId, DroneCore>, // Drones of the network, by ID
+    endpoints: BTreeSet<NodeId>,         // IDs of the clients and servers
+    links: BTreeSet<(NodeId, NodeId)>,   // Open channels, from the first node to the second
+    default_latency: Duration,           // Latency of the links without a latency set
+    scheduled: u64,                      // Inputs scheduled so far, orders inputs with same time
+    now: Duration,                       // Virtual time elapsed since the start
+    epoch: Instant,                      // Instant of the start, used as the clock of the drones
+    timeline: Vec<(Duration, SimEvent)>, // What happened so far, in order
+    // Latency of the links, with the lower ID first
+    latencies: HashMap<(NodeId, NodeId), Duration>,
+    // Inputs waiting to be handled, earliest first
+    queue: BinaryHeap<Reverse<Scheduled>>,
+}
+
+impl Simulator {
+    /// Create a simulation of the network described by a configuration, using the PDR and the
+    /// neighbors in the configuration. The random number generator of each drone is seeded from
+    /// `seed`, see [`RustafarianDroneBuilder::global_seed`].
+    pub fn from_config(config: &Config, seed: u64) -> Self {
+        Self::from_config_with(config, seed, |builder| builder)
+    }
+
+    /// Same as [`Simulator::from_config`], but every drone builder is passed to `configure`
+    /// before the drone is created, so that additional settings can be applied. The settings of
+    /// the runtime are ignored, see [`RustafarianDroneBuilder::build_core`].
+    pub fn from_config_with<F>(config: &Config, seed: u64, configure: F) -> Self
+    where
+        F: Fn(RustafarianDroneBuilder) -> RustafarianDroneBuilder,
+    {
+        let mut links = BTreeSet::new();
+        let mut drones = BTreeMap::new();
+        for drone in &config.drone {
+            links.extend(drone.connected_node_ids.iter().map(|id| (drone.id, *id)));
+
+            // The core only needs the IDs of the neighbors, the channels are never used
+            let neighbors = drone
+                .connected_node_ids
+                .iter()
+                .map(|id| (*id, unbounded().0))
+                .collect();
+            let builder = RustafarianDroneBuilder::new(
+                drone.id,
+                unbounded().0,
+                unbounded().1,
+                unbounded().1,
+                neighbors,
+            )
+            .pdr(drone.pdr)
+            .global_seed(seed);
+            drones.insert(drone.id, configure(builder).build_core());
+        }
+
+        let endpoints = config
+            .client
+            .iter()
+            .map(|client| (client.id, &client.connected_drone_ids))
+            .chain(
+                config
+                    .server
+                    .iter()
+                    .map(|server| (server.id, &server.connected_drone_ids)),
+            );
+        let mut endpoint_ids = BTreeSet::new();
+        for (id, neighbors) in endpoints {
+            links.extend(neighbors.iter().map(|neighbor| (id, *neighbor)));
+            endpoint_ids.insert(id);
+        }
+
+        Self {
+            drones,
+            endpoints: endpoint_ids,
+            links,
+            latencies: HashMap::new(),
+            default_latency: DEFAULT_LATENCY,
+            queue: BinaryHeap::new(),
+            scheduled: 0,
+            now: Duration::ZERO,
+            epoch: Instant::now(),
+            timeline: Vec::new(),
+        }
+    }
+
+    /// Set the latency of the link between two nodes, in both directions. Only affects the
+    /// packets sent from now on.
+    pub fn set_latency(&mut self, a: NodeId, b: NodeId, latency: Duration) {
+        self.latencies.insert(link_key(a, b), latency);
+    }
+
+    /// Set the latency of the links whose latency is not set.
+    /// Default: [`DEFAULT_LATENCY`]
+    pub fn set_default_latency(&mut self, latency: Duration) {
+        self.default_latency = latency;
+    }
+
+    /// The latency of the link between two nodes
+    pub fn latency(&self, a: NodeId, b: NodeId) -> Duration {
+        self.latencies
+            .get(&link_key(a, b))
+            .copied()
+            .unwrap_or(self.default_latency)
+    }
+
+    /// Virtual time elapsed since the start of the simulation
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Send a packet from a node to another, as a client or a server would. The packet arrives
+    /// after the latency of the link.
+    ///
+    /// ### Arguments
+    ///
+    /// * `from: NodeId` - The node sending the packet
+    /// * `to: NodeId` - The node receiving the packet
+    /// * `packet: Packet` - The packet to send
+    pub fn send(&mut self, from: NodeId, to: NodeId, packet: Packet) {
+        self.send_at(self.now, from, to, packet);
+    }
+
+    /// Same as [`Simulator::send`], with the packet sent at a later virtual time.
+    ///
+    /// ### Arguments
+    ///
+    /// * `time: Duration` - When the packet is sent, since the start of the simulation. Times in
+    ///   the past are treated as now
+    /// * `from: NodeId` - The node sending the packet
+    /// * `to: NodeId` - The node receiving the packet
+    /// * `packet: Packet` - The packet to send
+    pub fn send_at(&mut self, time: Duration, from: NodeId, to: NodeId, packet: Packet) {
+        let arrival = time.max(self.now) + self.latency(from, to);
+        self.schedule(arrival, Input::Packet(to, packet));
+    }
+
+    /// Send a packet from the node at `hop_index - 1` in its routing header to the one at
+    /// `hop_index`. Flood requests are not routed, use [`Simulator::send`] for them.
+    pub fn inject(&mut self, packet: Packet) {
+        let header = &packet.routing_header;
+        let (Some(&from), Some(&to)) = (
+            header
+                .hop_index
+                .checked_sub(1)
+                .and_then(|index| header.hops.get(index)),
+            header.hops.get(header.hop_index),
+        ) else {
+            panic!("the packet is not routed from a node to another");
+        };
+        self.send(from, to, packet);
+    }
+
+    /// Send a command to a drone, handled now.
+    pub fn command(&mut self, drone: NodeId, command: DroneCommand) {
+        self.command_at(self.now, drone, command);
+    }
+
+    /// Send a command to a drone, handled at a later virtual time. Times in the past are treated
+    /// as now.
+    pub fn command_at(&mut self, time: Duration, drone: NodeId, command: DroneCommand) {
+        self.schedule(time.max(self.now), Input::Command(drone, command));
+    }
+
+    /// Crash a drone now, as the Simulation Controller would: the drone is crashed, then removed
+    /// from its neighbors, and its neighbors from it.
+    pub fn crash(&mut self, drone: NodeId) {
+        self.command(drone, DroneCommand::Crash);
+        let neighbors: Vec<NodeId> = self
+            .links
+            .iter()
+            .filter(|(from, _)| *from == drone)
+            .map(|(_, to)| *to)
+            .collect();
+        for neighbor in neighbors {
+            if self.drones.contains_key(&neighbor) {
+                self.command(neighbor, DroneCommand::RemoveSender(drone));
+            } else {
+                self.links.remove(&(neighbor, drone));
+            }
+            self.command(drone, DroneCommand::RemoveSender(neighbor));
+        }
+    }
+
+    /// Handle the next input, moving the virtual clock to its time.
+    ///
+    /// ### Returns
+    /// `false` if there was nothing left to handle
+    pub fn step(&mut self) -> bool {
+        let Some(Reverse(scheduled)) = self.queue.pop() else {
+            return false;
+        };
+        self.now = scheduled.time;
+
+        match scheduled.input {
+            Input::Packet(to, packet) => self.deliver(to, packet),
+            Input::Command(drone, command) => self.handle_command(drone, command),
+        }
+        true
+    }
+
+    /// Run the simulation until there is nothing left to handle.
+    pub fn run(&mut self) {
+        while self.step() {}
+    }
+
+    /// Run the simulation up to a virtual time: every input scheduled up to `time` is handled,
+    /// then the clock is moved to `time`.
+    pub fn run_until(&mut self, time: Duration) {
+        while self
+            .queue
+            .peek()
+            .is_some_and(|Reverse(scheduled)| scheduled.time <= time)
+        {
+            self.step();
+        }
+        self.now = self.now.max(time);
+    }
+
+    /// Same as [`Simulator::run_until`], for a duration from now
+    pub fn run_for(&mut self, duration: Duration) {
+        self.run_until(self.now + duration);
+    }
+
+    /// What happened so far, with the virtual time of each event
+    pub fn timeline(&self) -> &[(Duration, SimEvent)] {
+        &self.timeline
+    }
+
+    /// Take what happened so far, leaving the timeline empty. Useful in long simulations, to
+    /// keep the memory bounded
+    pub fn take_timeline(&mut self) -> Vec<(Duration, SimEvent)> {
+        mem::take(&mut self.timeline)
+    }
+
+    /// Packets delivered to a client or a server so far, in order
+    pub fn delivered(&self, node: NodeId) -> Vec<&Packet> {
+        self.timeline
+            .iter()
+            .filter_map(|(_, event)| match event {
+                SimEvent::Delivered(id, packet) if *id == node => Some(packet),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// A copy of the statistics of a drone
+    pub fn stats(&self, drone: NodeId) -> DroneStats {
+        self.drones
+            .get(&drone)
+            .unwrap_or_else(|| panic!("drone {} is not in the network", drone))
+            .stats()
+            .snapshot()
+    }
+
+    fn schedule(&mut self, time: Duration, input: Input) {
+        self.queue.push(Reverse(Scheduled {
+            time,
+            seq: self.scheduled,
+            input,
+        }));
+        self.scheduled += 1;
+    }
+
+    /// A packet reaches a node: drones handle it, clients and servers record it. Packets sent to
+    /// a node that is not in the network are lost.
+    fn deliver(&mut self, to: NodeId, packet: Packet) {
+        let now = self.epoch + self.now;
+        let Some(core) = self.drones.get_mut(&to) else {
+            if self.endpoints.contains(&to) {
+                self.timeline
+                    .push((self.now, SimEvent::Delivered(to, packet)));
+            }
+            return;
+        };
+
+        core.set_clock(now);
+        let actions = core.on_packet(packet);
+        self.execute(to, actions);
+    }
+
+    /// Handle a command, opening or closing the channels of the drone as the runtime would.
+    fn handle_command(&mut self, drone: NodeId, command: DroneCommand) {
+        let Some(core) = self.drones.get_mut(&drone) else {
+            return;
+        };
+        core.set_clock(self.epoch + self.now);
+
+        match command {
+            DroneCommand::AddSender(node_id, _) if !core.is_crashed() => {
+                self.links.insert((drone, node_id));
+            }
+            DroneCommand::RemoveSender(node_id) => {
+                self.links.remove(&(drone, node_id));
+            }
+            _ => {}
+        }
+        let actions = core.on_command(command);
+        self.execute(drone, actions);
+    }
+
+    /// Carry out the actions of a drone. Packets sent on an open channel are always accepted,
+    /// and arrive after the latency of the link.
+    fn execute(&mut self, drone: NodeId, actions: Vec<Action>) {
+        for action in actions {
+            match action {
+                Action::Send { to, packet } => {
+                    let outcome = if self.links.contains(&(drone, to)) {
+                        let arrival = self.now + self.latency(drone, to);
+                        self.schedule(arrival, Input::Packet(to, packet.clone()));
+                        self.core(drone).on_sent(to, packet)
+                    } else {
+                        self.core(drone)
+                            .on_send_failed(packet, to, SendFailure::Disconnected)
+                    };
+                    self.execute(drone, outcome);
+                }
+                Action::Event(event) => {
+                    self.timeline
+                        .push((self.now, SimEvent::Drone(drone, event)));
+                }
+                Action::Shortcut(packet) => self.shortcut(drone, packet),
+            }
+        }
+    }
+
+    /// Deliver a packet sent to the Simulation Controller to its destination, immediately.
+    fn shortcut(&mut self, drone: NodeId, packet: Packet) {
+        let destination = packet.routing_header.hops.last().copied();
+        self.timeline.push((
+            self.now,
+            SimEvent::Drone(drone, DroneEvent::ControllerShortcut(packet.clone())),
+        ));
+        if let Some(destination) = destination.filter(|id| self.endpoints.contains(id)) {
+            self.timeline
+                .push((self.now, SimEvent::Delivered(destination, packet)));
+        }
+    }
+
+    fn core(&mut self, drone: NodeId) -> &mut DroneCore {
+        self.drones
+            .get_mut(&drone)
+            .expect("only drones produce actions")
+    }
+}
+
+/// Key of the latency of a link, the same in both directions
+fn link_key(a: NodeId, b: NodeId) -> (NodeId, NodeId) {
+    (a.min(b), a.max(b))
+}
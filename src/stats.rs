@@ -0,0 +1,182 @@
+use crate::packet_type_name;
+use crate::priority::Turn;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, Packet};
+
+/// Counters describing the traffic handled by a drone.
+///
+/// Packet types and NACK types are identified by the name of the variant, e.g. `"MsgFragment"`
+/// or `"ErrorInRouting"`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DroneStats {
+    /// Packets received from other nodes, per packet type
+    pub received: HashMap<&'static str, u64>,
+    /// Packets sent to a neighbor, per packet type
+    pub sent: HashMap<&'static str, u64>,
+    /// Packets sent, per neighbor
+    pub sent_per_neighbor: HashMap<NodeId, u64>,
+    /// Fragments checked against the drop policy
+    pub drop_checks: u64,
+    /// Fragments dropped by the drop policy
+    pub dropped: u64,
+    /// Fragments dropped, per neighbor they were supposed to reach
+    pub dropped_per_neighbor: HashMap<NodeId, u64>,
+    /// NACKs created by the drone, per NACK type
+    pub nacks: HashMap<&'static str, u64>,
+    /// Packets sent to the Simulation Controller through a `ControllerShortcut`
+    pub shortcuts: u64,
+    /// Flood responses created by the drone
+    pub flood_responses: u64,
+    /// Malformed packets rejected before being handled
+    pub rejected: u64,
+    /// Control packets handled before fragments that were waiting, in priority mode
+    pub prioritized: u64,
+    /// Fragments handled before control packets that were waiting, in priority mode, so that
+    /// fragments are not starved
+    pub fair_turns: u64,
+    /// Largest number of packets waiting to be handled, in priority mode
+    pub max_queued: usize,
+    /// Packets that found the channel of a neighbor full
+    pub congested: u64,
+    /// Packets that found the channel of a neighbor full, per neighbor
+    pub congested_per_neighbor: HashMap<NodeId, u64>,
+    /// Packets dropped because the channel of a neighbor was full
+    pub congestion_drops: u64,
+    /// Packets held in an overflow queue, waiting for room in the channel of a neighbor
+    pub overflowed: u64,
+    /// Largest number of packets held for a single neighbor
+    pub max_overflow: usize,
+    /// Why the drone stopped running, `None` while it's running
+    pub stopped: Option<StopReason>,
+}
+
+/// Reason why the run loop of a drone returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The drone crashed, handled every packet left in its queue and all its neighbors were
+    /// removed or its controller channel was closed, or its packet channel was closed
+    Crashed,
+    /// Both the controller channel and the packet channel were closed by every sender
+    Disconnected,
+}
+
+impl DroneStats {
+    /// The observed drop rate: dropped fragments over fragments checked against the drop policy.
+    /// Returns `0.0` if no fragment has been checked.
+    pub fn drop_rate(&self) -> f64 {
+        if self.drop_checks == 0 {
+            0.0
+        } else {
+            self.dropped as f64 / self.drop_checks as f64
+        }
+    }
+
+    /// Total number of packets received
+    pub fn total_received(&self) -> u64 {
+        self.received.values().sum()
+    }
+
+    /// Total number of packets sent
+    pub fn total_sent(&self) -> u64 {
+        self.sent.values().sum()
+    }
+
+    pub(crate) fn record_received(&mut self, packet: &Packet) {
+        *self
+            .received
+            .entry(packet_type_name(&packet.pack_type))
+            .or_default() += 1;
+    }
+
+    pub(crate) fn record_sent(&mut self, packet_type: &'static str, neighbor: NodeId) {
+        *self.sent.entry(packet_type).or_default() += 1;
+        *self.sent_per_neighbor.entry(neighbor).or_default() += 1;
+    }
+
+    pub(crate) fn record_drop_check(&mut self, neighbor: NodeId, dropped: bool) {
+        self.drop_checks += 1;
+        if dropped {
+            self.dropped += 1;
+            *self.dropped_per_neighbor.entry(neighbor).or_default() += 1;
+        }
+    }
+
+    pub(crate) fn record_nack(&mut self, nack_type: &NackType) {
+        *self.nacks.entry(nack_type_name(nack_type)).or_default() += 1;
+    }
+
+    pub(crate) fn record_shortcut(&mut self) {
+        self.shortcuts += 1;
+    }
+
+    pub(crate) fn record_flood_response(&mut self) {
+        self.flood_responses += 1;
+    }
+
+    pub(crate) fn record_rejected(&mut self) {
+        self.rejected += 1;
+    }
+
+    pub(crate) fn record_turn(&mut self, turn: Turn) {
+        match turn {
+            Turn::Alone => {}
+            Turn::Prioritized => self.prioritized += 1,
+            Turn::Fair => self.fair_turns += 1,
+        }
+    }
+
+    pub(crate) fn record_queued(&mut self, queued: usize) {
+        self.max_queued = self.max_queued.max(queued);
+    }
+
+    pub(crate) fn record_congestion(&mut self, neighbor: NodeId) {
+        self.congested += 1;
+        *self.congested_per_neighbor.entry(neighbor).or_default() += 1;
+    }
+
+    pub(crate) fn record_congestion_drop(&mut self) {
+        self.congestion_drops += 1;
+    }
+
+    pub(crate) fn record_overflow(&mut self, held: usize) {
+        self.overflowed += 1;
+        self.max_overflow = self.max_overflow.max(held);
+    }
+}
+
+/// Name of the type of a NACK, used as key in the statistics.
+pub fn nack_type_name(nack_type: &NackType) -> &'static str {
+    match nack_type {
+        NackType::ErrorInRouting(_) => "ErrorInRouting",
+        NackType::DestinationIsDrone => "DestinationIsDrone",
+        NackType::Dropped => "Dropped",
+        NackType::UnexpectedRecipient(_) => "UnexpectedRecipient",
+    }
+}
+
+/// Shared handle to the statistics of a drone, used to read them from outside the drone thread.
+///
+/// Get it with [`RustafarianDrone::stats`](crate::RustafarianDrone::stats) before moving the
+/// drone to its thread.
+#[derive(Debug, Clone, Default)]
+pub struct StatsHandle {
+    stats: Arc<Mutex<DroneStats>>,
+}
+
+impl StatsHandle {
+    /// A copy of the current statistics
+    pub fn snapshot(&self) -> DroneStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Reset every counter to zero
+    pub fn reset(&self) {
+        *self.stats.lock().unwrap() = DroneStats::default();
+    }
+
+    pub(crate) fn update<F: FnOnce(&mut DroneStats)>(&self, update: F) {
+        update(&mut self.stats.lock().unwrap());
+    }
+}
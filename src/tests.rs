@@ -1,7 +1,31 @@
+mod builder;
+mod capture;
+mod congestion;
+mod crash;
+mod dead_end;
+mod drone_core;
+mod drop_policy;
+mod egress;
+mod fixtures;
 mod flood;
+mod flood_cache;
 mod fragments;
+mod harness;
+mod initializer;
+mod link_pdr;
+mod logging;
+mod nack_route;
+mod priority;
+mod replay;
+mod routing_errors;
+mod run_loop;
 mod rusty_drone_tests;
+mod seed;
 mod simcontroller;
+mod simulator;
+mod stats;
+mod tracing;
+mod validation;
 mod wg_tests;
 
 fn run_all_tests() {
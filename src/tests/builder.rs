@@ -0,0 +1,188 @@
+/// Run tests on the drone builder
+#[cfg(test)]
+mod builder_tests {
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::unbounded;
+    use std::collections::HashMap;
+    use std::thread;
+    use wg_2024::drone::Drone;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{FloodRequest, Fragment, Nack, NackType, NodeType, Packet, PacketType};
+
+    fn create_fragment(fragment_index: u64) -> Packet {
+        Packet {
+            pack_type: PacketType::MsgFragment(Fragment {
+                fragment_index,
+                total_n_fragments: 1,
+                length: 128,
+                data: [2; 128],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 21],
+            },
+            session_id: 1,
+        }
+    }
+
+    fn create_flood_request(flood_id: u64) -> Packet {
+        Packet {
+            pack_type: PacketType::FloodRequest(FloodRequest {
+                flood_id,
+                initiator_id: 1,
+                path_trace: vec![(1, NodeType::Client)],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 0,
+                hops: vec![],
+            },
+            session_id: flood_id,
+        }
+    }
+
+    /// Check that a drone created with the builder forwards a fragment
+    #[test]
+    fn run_builder_forward() {
+        let (c_send, _c_recv) = unbounded();
+        let (s_send, s_recv) = unbounded();
+        let (d1_send, d1_recv) = unbounded();
+        let (_d_command_send, d_command_recv) = unbounded();
+
+        let neighbours = HashMap::from([(1, c_send), (21, s_send)]);
+
+        let mut drone =
+            RustafarianDroneBuilder::new(11, unbounded().0, d_command_recv, d1_recv, neighbours)
+                .log_level(LogLevel::NONE)
+                .build();
+
+        thread::spawn(move || {
+            drone.run();
+        });
+
+        let mut msg = create_fragment(1);
+        d1_send.send(msg.clone()).unwrap();
+        msg.routing_header.hop_index = 2;
+
+        assert_eq!(s_recv.recv().unwrap(), msg);
+    }
+
+    /// Check that the PDR set with the builder is used by the drone
+    #[test]
+    fn run_builder_pdr() {
+        let (c_send, c_recv) = unbounded();
+        let (s_send, _s_recv) = unbounded();
+        let (d1_send, d1_recv) = unbounded();
+        let (_d_command_send, d_command_recv) = unbounded();
+
+        let neighbours = HashMap::from([(1, c_send), (21, s_send)]);
+
+        let mut drone =
+            RustafarianDroneBuilder::new(11, unbounded().0, d_command_recv, d1_recv, neighbours)
+                .pdr(1.0)
+                .log_level(LogLevel::NONE)
+                .build();
+
+        thread::spawn(move || {
+            drone.run();
+        });
+
+        d1_send.send(create_fragment(1)).unwrap();
+
+        let expected_nack = Packet {
+            pack_type: PacketType::Nack(Nack {
+                fragment_index: 1,
+                nack_type: NackType::Dropped,
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![11, 1],
+            },
+            session_id: 1,
+        };
+
+        assert_eq!(c_recv.recv().unwrap(), expected_nack);
+    }
+
+    /// Check that two drones with the same seed drop the same fragments
+    #[test]
+    fn run_builder_seed() {
+        let mut dropped_per_drone = vec![];
+
+        for _ in 0..2 {
+            let (c_send, c_recv) = unbounded();
+            let (s_send, s_recv) = unbounded();
+            let (d1_send, d1_recv) = unbounded();
+            let (_d_command_send, d_command_recv) = unbounded();
+
+            let neighbours = HashMap::from([(1, c_send), (21, s_send)]);
+
+            let mut drone = RustafarianDroneBuilder::new(
+                11,
+                unbounded().0,
+                d_command_recv,
+                d1_recv,
+                neighbours,
+            )
+            .pdr(0.5)
+            .seed(42)
+            .log_level(LogLevel::NONE)
+            .build();
+
+            thread::spawn(move || {
+                drone.run();
+            });
+
+            let mut dropped = vec![];
+            for fragment_index in 0..50 {
+                d1_send.send(create_fragment(fragment_index)).unwrap();
+                crossbeam_channel::select! {
+                    recv(c_recv) -> nack => dropped.push(nack.unwrap()),
+                    recv(s_recv) -> _fragment => {},
+                }
+            }
+
+            dropped_per_drone.push(dropped);
+        }
+
+        assert_eq!(dropped_per_drone[0], dropped_per_drone[1]);
+    }
+
+    /// Check that when the flood cache is full, the oldest flood request is forgotten
+    #[test]
+    fn run_builder_flood_cache_capacity() {
+        let (c_send, c_recv) = unbounded();
+        let (d2_send, d2_recv) = unbounded();
+        let (d1_send, d1_recv) = unbounded();
+        let (_d_command_send, d_command_recv) = unbounded();
+
+        let neighbours = HashMap::from([(1, c_send), (12, d2_send)]);
+
+        let mut drone =
+            RustafarianDroneBuilder::new(11, unbounded().0, d_command_recv, d1_recv, neighbours)
+                .flood_cache_capacity(1)
+                .log_level(LogLevel::NONE)
+                .build();
+
+        thread::spawn(move || {
+            drone.run();
+        });
+
+        d1_send.send(create_flood_request(1)).unwrap();
+        d1_send.send(create_flood_request(2)).unwrap();
+        // Flood 1 has been evicted by flood 2, so it's handled as a new request
+        d1_send.send(create_flood_request(1)).unwrap();
+
+        for expected_flood_id in [1, 2, 1] {
+            match d2_recv.recv().unwrap().pack_type {
+                PacketType::FloodRequest(request) => {
+                    assert_eq!(request.flood_id, expected_flood_id)
+                }
+                other => panic!("Expected a flood request, received {:?}", other),
+            }
+        }
+        assert!(
+            c_recv.try_recv().is_err(),
+            "No response should be sent back"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+/// Run tests on the capture of the packets crossing a drone
+#[cfg(test)]
+mod capture_tests {
+    use crate::capture::{
+        read_capture, CaptureError, CaptureReader, CaptureRecord, CaptureWriter, Direction,
+    };
+    use crate::tests::fixtures::create_fragment;
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::unbounded;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::{env, fs};
+    use wg_2024::packet::{NackType, PacketType};
+
+    fn capture_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rustafarian-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Check that the packets received and sent by the drone are recorded in order, with the
+    /// neighbor they came from or were sent to
+    #[test]
+    fn run_capture_drone() {
+        let dir = capture_dir("capture");
+        let (c_send, _c_recv) = unbounded();
+        let (d2_send, _d2_recv) = unbounded();
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send), (12, d2_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .capture(CaptureWriter::in_dir(&dir, 11).unwrap())
+        .build();
+
+        drone.handle_packet(create_fragment(0, vec![1, 11, 12]));
+        // 21 is not a neighbor, the fragment is NACKed
+        drone.handle_packet(create_fragment(1, vec![1, 11, 21]));
+
+        let records = read_capture(dir.join("capture-11.jsonl")).unwrap();
+        let summary: Vec<(Direction, Option<u8>, &str)> = records
+            .iter()
+            .map(|record| {
+                let packet_type = crate::packet_type_name(&record.packet.pack_type);
+                (record.direction, record.neighbor, packet_type)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Direction::Ingress, Some(1), "MsgFragment"),
+                (Direction::Egress, Some(12), "MsgFragment"),
+                (Direction::Ingress, Some(1), "MsgFragment"),
+                (Direction::Egress, Some(1), "Nack"),
+            ]
+        );
+        assert!(records.iter().all(|record| record.drone == 11));
+        assert_eq!(records[0].packet, create_fragment(0, vec![1, 11, 12]));
+        assert_eq!(records[1].packet.routing_header.hop_index, 2);
+        match &records[3].packet.pack_type {
+            PacketType::Nack(nack) => assert_eq!(nack.nack_type, NackType::ErrorInRouting(21)),
+            other => panic!("Expected a NACK, received {:?}", other),
+        }
+        assert!(records
+            .windows(2)
+            .all(|pair| pair[0].timestamp_us <= pair[1].timestamp_us));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Check that the file is rotated by size, keeping only the given number of old files, and
+    /// that the records are read back oldest first
+    #[test]
+    fn run_capture_rotation() {
+        let dir = capture_dir("rotation");
+        let path = dir.join("capture.jsonl");
+        let record = |index| {
+            CaptureRecord::new(
+                11,
+                Direction::Ingress,
+                Some(1),
+                create_fragment(index, vec![1, 11, 12]),
+            )
+        };
+        let record_size = serde_json::to_vec(&record(0)).unwrap().len() as u64 + 1;
+
+        // Two records per file, two rotated files
+        let mut writer = CaptureWriter::new(&path)
+            .unwrap()
+            .rotate(record_size * 2, 2);
+        for index in 0..7 {
+            writer.write(&record(index)).unwrap();
+        }
+
+        let exists = |suffix: &str| dir.join(format!("capture.jsonl{}", suffix)).exists();
+        assert!(exists("") && exists(".1") && exists(".2"));
+        assert!(!exists(".3"));
+
+        let indexes: Vec<u64> = read_capture(&path)
+            .unwrap()
+            .iter()
+            .map(|record| match &record.packet.pack_type {
+                PacketType::MsgFragment(fragment) => fragment.fragment_index,
+                other => panic!("Expected a fragment, received {:?}", other),
+            })
+            .collect();
+        // Records 0 and 1 were in the file deleted by the last rotation
+        assert_eq!(indexes, [2, 3, 4, 5, 6]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Check that an invalid line is reported with its number, skipping empty lines
+    #[test]
+    fn run_capture_reader_errors() {
+        let record = CaptureRecord::new(
+            11,
+            Direction::Egress,
+            Some(12),
+            create_fragment(0, vec![1, 11, 12]),
+        );
+        let content = format!(
+            "{}\n\n{{\"drone\": 11}}\n",
+            serde_json::to_string(&record).unwrap()
+        );
+
+        let mut reader = CaptureReader::new(content.as_bytes());
+        assert_eq!(reader.next().unwrap().unwrap(), record);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(CaptureError::Parse(3, _)))
+        ));
+        assert!(reader.next().is_none());
+    }
+}
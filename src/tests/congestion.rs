@@ -0,0 +1,193 @@
+/// Run tests on neighbors with bounded channels
+#[cfg(test)]
+mod congestion_tests {
+    use crate::congestion::FullQueuePolicy;
+    use crate::{LogLevel, RustafarianDrone, RustafarianDroneBuilder};
+    use crossbeam_channel::{bounded, unbounded, Receiver};
+    use std::collections::HashMap;
+    use std::thread;
+    use std::time::Duration;
+    use wg_2024::controller::DroneEvent;
+    use wg_2024::drone::Drone;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, Fragment, NackType, Packet, PacketType};
+
+    fn create_packet(pack_type: PacketType) -> Packet {
+        Packet {
+            pack_type,
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 12],
+            },
+            session_id: 1,
+        }
+    }
+
+    fn create_fragment(fragment_index: u64) -> Packet {
+        create_packet(PacketType::MsgFragment(Fragment {
+            fragment_index,
+            total_n_fragments: 10,
+            length: 128,
+            data: [1; 128],
+        }))
+    }
+
+    /// Create drone 11 between client 1 and drone 12, whose channel has room for a single packet
+    /// and is already full.
+    ///
+    /// ### Arguments
+    ///
+    /// * `policy: FullQueuePolicy` - What the drone does when the channel of drone 12 is full
+    /// * `send_timeout: Option<Duration>` - How long the drone waits for room, `None` = no wait
+    /// ---
+    /// ### Returns
+    /// The drone, the channels of the client, of drone 12 and of the controller
+    fn create_drone(
+        policy: FullQueuePolicy,
+        send_timeout: Option<Duration>,
+    ) -> (
+        RustafarianDrone,
+        Receiver<Packet>,
+        Receiver<Packet>,
+        Receiver<DroneEvent>,
+    ) {
+        let (c_send, c_recv) = unbounded();
+        let (d2_send, d2_recv) = bounded(1);
+        let (controller_send, controller_recv) = unbounded();
+        d2_send.send(create_fragment(100)).unwrap();
+
+        let mut builder = RustafarianDroneBuilder::new(
+            11,
+            controller_send,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send), (12, d2_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .full_queue_policy(policy);
+        if let Some(send_timeout) = send_timeout {
+            builder = builder.send_timeout(send_timeout);
+        }
+        let drone = builder.build();
+
+        (drone, c_recv, d2_recv, controller_recv)
+    }
+
+    fn fragment_index(packet: &Packet) -> u64 {
+        match &packet.pack_type {
+            PacketType::MsgFragment(fragment) => fragment.fragment_index,
+            other => panic!("Expected a fragment, received {:?}", other),
+        }
+    }
+
+    /// Check that with the `Drop` policy a fragment is NACKed and an ACK is sent to the
+    /// Simulation Controller, counting the congestion
+    #[test]
+    fn run_congestion_drop() {
+        let (mut drone, c_recv, d2_recv, controller_recv) =
+            create_drone(FullQueuePolicy::Drop, None);
+        let stats = drone.stats();
+
+        drone.handle_packet(create_fragment(3));
+        match c_recv.try_recv().unwrap().pack_type {
+            PacketType::Nack(nack) => {
+                assert_eq!(nack.nack_type, NackType::Dropped);
+                assert_eq!(nack.fragment_index, 3);
+            }
+            other => panic!("Expected a NACK, received {:?}", other),
+        }
+        assert!(controller_recv
+            .try_iter()
+            .any(|event| matches!(event, DroneEvent::PacketDropped(_))));
+
+        let ack = create_packet(PacketType::Ack(Ack { fragment_index: 3 }));
+        drone.handle_packet(ack.clone());
+        let mut shortcut = ack;
+        shortcut.routing_header.hop_index = 2;
+        assert!(controller_recv
+            .try_iter()
+            .any(|event| event == DroneEvent::ControllerShortcut(shortcut.clone())));
+
+        // Only the packet that filled the channel
+        assert_eq!(d2_recv.len(), 1);
+        let stats = stats.snapshot();
+        assert_eq!(stats.congested, 2);
+        assert_eq!(stats.congested_per_neighbor[&12], 2);
+        assert_eq!(stats.congestion_drops, 2);
+    }
+
+    /// Check that with the `Overflow` policy the packets are held in order, and dropped once the
+    /// overflow queue is full
+    #[test]
+    fn run_congestion_overflow() {
+        let (mut drone, c_recv, d2_recv, _controller_recv) =
+            create_drone(FullQueuePolicy::Overflow { capacity: 2 }, None);
+        let stats = drone.stats();
+
+        for index in 0..3 {
+            drone.handle_packet(create_fragment(index));
+        }
+        // The third fragment didn't fit in the overflow queue
+        match c_recv.try_recv().unwrap().pack_type {
+            PacketType::Nack(nack) => assert_eq!(nack.fragment_index, 2),
+            other => panic!("Expected a NACK, received {:?}", other),
+        }
+
+        assert_eq!(fragment_index(&d2_recv.try_recv().unwrap()), 100);
+        drone.flush_overflows();
+        assert_eq!(fragment_index(&d2_recv.try_recv().unwrap()), 0);
+        // A new packet is sent after the ones already held, even if the channel has room
+        drone.handle_packet(create_fragment(4));
+        assert_eq!(fragment_index(&d2_recv.try_recv().unwrap()), 1);
+        drone.flush_overflows();
+        assert_eq!(fragment_index(&d2_recv.try_recv().unwrap()), 4);
+
+        let stats = stats.snapshot();
+        assert_eq!(stats.congested, 4);
+        assert_eq!(stats.overflowed, 3);
+        assert_eq!(stats.max_overflow, 2);
+        assert_eq!(stats.congestion_drops, 1);
+        assert_eq!(stats.sent["MsgFragment"], 3);
+    }
+
+    /// Check that a running drone sends the packets held as soon as the neighbor has room, and
+    /// that with a timeout the drone waits for room before dropping
+    #[test]
+    fn run_congestion_running_drone() {
+        let (d1_send, d1_recv) = unbounded();
+        let (d2_send, d2_recv) = bounded(1);
+        d2_send.send(create_fragment(100)).unwrap();
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            d1_recv,
+            HashMap::from([(1, unbounded().0), (12, d2_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .full_queue_policy(FullQueuePolicy::Overflow { capacity: 10 })
+        .build();
+        thread::spawn(move || drone.run());
+
+        for index in 0..5 {
+            d1_send.send(create_fragment(index)).unwrap();
+        }
+        let received: Vec<u64> = (0..6)
+            .map(|_| fragment_index(&d2_recv.recv_timeout(Duration::from_secs(1)).unwrap()))
+            .collect();
+        assert_eq!(received, [100, 0, 1, 2, 3, 4]);
+
+        let (mut drone, c_recv, d2_recv, _controller_recv) =
+            create_drone(FullQueuePolicy::Drop, Some(Duration::from_secs(1)));
+        let reader = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            d2_recv.recv().unwrap();
+            d2_recv
+        });
+        drone.handle_packet(create_fragment(0));
+        let d2_recv = reader.join().unwrap();
+        assert_eq!(fragment_index(&d2_recv.try_recv().unwrap()), 0);
+        assert!(c_recv.try_recv().is_err());
+    }
+}
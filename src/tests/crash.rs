@@ -0,0 +1,178 @@
+/// Run tests on the crash of the drone
+#[cfg(test)]
+mod crash_tests {
+    use crate::stats::StopReason;
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::{unbounded, RecvTimeoutError};
+    use std::collections::HashMap;
+    use std::thread;
+    use std::time::Duration;
+    use wg_2024::controller::DroneCommand;
+    use wg_2024::drone::Drone;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, Fragment, NackType, Packet, PacketType};
+
+    fn create_packet(pack_type: PacketType) -> Packet {
+        Packet {
+            pack_type,
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 12],
+            },
+            session_id: 1,
+        }
+    }
+
+    /// Check the crashing phase: fragments are NACKed, ACKs forwarded, only `RemoveSender` is
+    /// handled, and the drone stops when the last neighbor is removed
+    #[test]
+    fn run_crash_draining() {
+        let (c_send, c_recv) = unbounded();
+        let (d2_send, d2_recv) = unbounded();
+        let (d1_send, d1_recv) = unbounded();
+        let (d_command_send, d_command_recv) = unbounded();
+        let (stopped_send, stopped_recv) = unbounded::<()>();
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            d_command_recv,
+            d1_recv,
+            HashMap::from([(1, c_send), (12, d2_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .build();
+        let stats = drone.stats();
+
+        thread::spawn(move || {
+            let _stopped_send = stopped_send;
+            drone.run();
+        });
+
+        d_command_send.send(DroneCommand::Crash).unwrap();
+        // Ignored while crashing
+        d_command_send
+            .send(DroneCommand::SetPacketDropRate(1.0))
+            .unwrap();
+        d_command_send
+            .send(DroneCommand::AddSender(13, unbounded().0))
+            .unwrap();
+
+        d1_send
+            .send(create_packet(PacketType::MsgFragment(Fragment {
+                fragment_index: 0,
+                total_n_fragments: 1,
+                length: 128,
+                data: [1; 128],
+            })))
+            .unwrap();
+        match c_recv
+            .recv_timeout(Duration::from_secs(1))
+            .unwrap()
+            .pack_type
+        {
+            PacketType::Nack(nack) => assert_eq!(nack.nack_type, NackType::ErrorInRouting(11)),
+            other => panic!("Expected a NACK, received {:?}", other),
+        }
+
+        // ACKs are still forwarded
+        d1_send
+            .send(create_packet(PacketType::Ack(Ack { fragment_index: 0 })))
+            .unwrap();
+        assert!(d2_recv.recv_timeout(Duration::from_secs(1)).is_ok());
+
+        // The sender of the drone is still alive, the drone stops because of the removals
+        d_command_send.send(DroneCommand::RemoveSender(1)).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(stats.snapshot().stopped, None, "Neighbor 12 is still there");
+
+        d_command_send.send(DroneCommand::RemoveSender(12)).unwrap();
+        assert_eq!(
+            stopped_recv.recv_timeout(Duration::from_secs(1)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+        assert_eq!(stats.snapshot().stopped, Some(StopReason::Crashed));
+        drop(d1_send);
+    }
+
+    /// Check that a crashed drone stops once its queue is empty when the controller channel is
+    /// closed, even if some neighbors were never removed and still hold its packet channel
+    #[test]
+    fn run_crash_controller_closed() {
+        let (c_send, c_recv) = unbounded();
+        let (d2_send, _d2_recv) = unbounded();
+        let (d1_send, d1_recv) = unbounded();
+        let (d_command_send, d_command_recv) = unbounded();
+        let (stopped_send, stopped_recv) = unbounded::<()>();
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            d_command_recv,
+            d1_recv,
+            HashMap::from([(1, c_send), (12, d2_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .build();
+        let stats = drone.stats();
+
+        thread::spawn(move || {
+            let _stopped_send = stopped_send;
+            drone.run();
+        });
+
+        d_command_send.send(DroneCommand::Crash).unwrap();
+        thread::sleep(Duration::from_millis(50));
+
+        // A packet still in the queue when the controller goes away is handled
+        d1_send
+            .send(create_packet(PacketType::MsgFragment(Fragment {
+                fragment_index: 0,
+                total_n_fragments: 1,
+                length: 128,
+                data: [1; 128],
+            })))
+            .unwrap();
+        drop(d_command_send);
+
+        match c_recv
+            .recv_timeout(Duration::from_secs(1))
+            .unwrap()
+            .pack_type
+        {
+            PacketType::Nack(nack) => assert_eq!(nack.nack_type, NackType::ErrorInRouting(11)),
+            other => panic!("Expected a NACK, received {:?}", other),
+        }
+        assert_eq!(
+            stopped_recv.recv_timeout(Duration::from_secs(1)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+        assert_eq!(stats.snapshot().stopped, Some(StopReason::Crashed));
+        // The neighbors still hold the packet channel
+        drop(d1_send);
+    }
+
+    /// Check that the commands received after the crash are ignored, except `RemoveSender`
+    #[test]
+    fn run_crash_ignored_commands() {
+        let (c_send, _c_recv) = unbounded();
+        let (d2_send, _d2_recv) = unbounded();
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .build();
+
+        drone.handle_command(DroneCommand::Crash);
+        drone.handle_command(DroneCommand::AddSender(12, d2_send));
+        assert!(!drone.neighbors.contains_key(&12));
+
+        drone.handle_command(DroneCommand::RemoveSender(1));
+        assert!(drone.neighbors.is_empty());
+    }
+}
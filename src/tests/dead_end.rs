@@ -0,0 +1,117 @@
+/// Run tests on the flood requests reaching a dead end
+#[cfg(test)]
+mod dead_end_tests {
+    use crate::harness::Topology;
+    use crate::tests::fixtures::create_flood_request;
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::{unbounded, Receiver};
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{FloodResponse, NodeType, Packet, PacketType};
+
+    fn expect_response(c_recv: &Receiver<Packet>) -> (FloodResponse, SourceRoutingHeader) {
+        let packet = c_recv
+            .recv_timeout(Duration::from_secs(1))
+            .expect("No flood response received");
+        match packet.pack_type {
+            PacketType::FloodResponse(response) => (response, packet.routing_header),
+            other => panic!("Expected a flood response, received {:?}", other),
+        }
+    }
+
+    /// Client 1 - 11 - 12 - 13: the last drone of the line answers the flood request
+    #[test]
+    fn run_dead_end_line() {
+        let harness = Topology::new()
+            .client(1)
+            .drone(11)
+            .drone(12)
+            .drone(13)
+            .link(1, 11)
+            .link(11, 12)
+            .link(12, 13)
+            .start();
+
+        harness.send(11, create_flood_request(1));
+
+        let (response, routing_header) =
+            expect_response(&harness.network().clients[&1].packet_recv);
+        assert_eq!(response.flood_id, 1);
+        assert_eq!(
+            response.path_trace,
+            vec![
+                (1, NodeType::Client),
+                (11, NodeType::Drone),
+                (12, NodeType::Drone),
+                (13, NodeType::Drone)
+            ]
+        );
+        assert_eq!(routing_header.hops, vec![13, 12, 11, 1]);
+        assert!(harness.delivered(1).is_empty());
+    }
+
+    /// Client 1 - 11, with the leaves 12, 13 and 14 around 11: every leaf answers the flood request
+    #[test]
+    fn run_dead_end_star() {
+        let harness = Topology::new()
+            .client(1)
+            .drone(11)
+            .drone(12)
+            .drone(13)
+            .drone(14)
+            .link(1, 11)
+            .link(11, 12)
+            .link(11, 13)
+            .link(11, 14)
+            .start();
+
+        harness.send(11, create_flood_request(1));
+
+        let mut leaves = Vec::new();
+        for _ in 0..3 {
+            let (response, routing_header) =
+                expect_response(&harness.network().clients[&1].packet_recv);
+            let leaf = response.path_trace.last().unwrap().0;
+            assert_eq!(
+                response.path_trace,
+                vec![
+                    (1, NodeType::Client),
+                    (11, NodeType::Drone),
+                    (leaf, NodeType::Drone)
+                ]
+            );
+            assert_eq!(routing_header.hops, vec![leaf, 11, 1]);
+            leaves.push(leaf);
+        }
+        leaves.sort();
+        assert_eq!(leaves, vec![12, 13, 14]);
+    }
+
+    /// A drone whose other neighbors can't be reached answers the flood request itself
+    #[test]
+    fn run_dead_end_unreachable_neighbors() {
+        let (c_send, c_recv) = unbounded();
+        let (d2_send, d2_recv) = unbounded::<Packet>();
+        drop(d2_recv);
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send), (12, d2_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .build();
+
+        drone.handle_packet(create_flood_request(1));
+
+        let (response, routing_header) = expect_response(&c_recv);
+        assert_eq!(
+            response.path_trace,
+            vec![(1, NodeType::Client), (11, NodeType::Drone)]
+        );
+        assert_eq!(routing_header.hops, vec![11, 1]);
+    }
+}
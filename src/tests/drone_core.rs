@@ -0,0 +1,207 @@
+/// Run tests on the actions returned by the drone core, without channels or threads
+#[cfg(test)]
+mod drone_core_tests {
+    use crate::drone_core::{Action, DroneCore, SendFailure};
+    use crate::tests::fixtures::{create_fragment, create_nack, create_packet, fragment};
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::unbounded;
+    use std::collections::HashMap;
+    use wg_2024::controller::{DroneCommand, DroneEvent};
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, FloodRequest, NackType, NodeType, PacketType};
+
+    /// Create the core of drone 11, with the given neighbors and PDR
+    fn create_core(neighbors: &[u8], pdr: f32) -> DroneCore {
+        let neighbors = neighbors
+            .iter()
+            .map(|&neighbor| (neighbor, unbounded().0))
+            .collect::<HashMap<_, _>>();
+        RustafarianDroneBuilder::new(11, unbounded().0, unbounded().1, unbounded().1, neighbors)
+            .pdr(pdr)
+            .seed(1)
+            .log_level(LogLevel::NONE)
+            .build_core()
+    }
+
+    /// Check that a fragment is handed to the runtime, and that the controller is notified only
+    /// once the runtime reports it as sent
+    #[test]
+    fn run_core_forward() {
+        let mut core = create_core(&[1, 12], 0.0);
+
+        let actions = core.on_packet(create_fragment(3, vec![1, 11, 12]));
+        let forwarded = create_packet(fragment(3), 2, vec![1, 11, 12]);
+        assert_eq!(
+            actions,
+            vec![Action::Send {
+                to: 12,
+                packet: forwarded.clone()
+            }]
+        );
+
+        let actions = core.on_sent(12, forwarded.clone());
+        assert_eq!(
+            actions,
+            vec![Action::Event(DroneEvent::PacketSent(forwarded))]
+        );
+        let stats = core.stats().snapshot();
+        assert_eq!(stats.sent["MsgFragment"], 1);
+        assert_eq!(stats.sent_per_neighbor[&12], 1);
+    }
+
+    /// Check the actions returned for fragments that are dropped or can't be sent
+    #[test]
+    fn run_core_failures() {
+        let mut core = create_core(&[1, 12], 1.0);
+
+        // Dropped by the PDR: the event, then the NACK back to the client
+        let actions = core.on_packet(create_fragment(3, vec![1, 11, 12]));
+        assert_eq!(
+            actions,
+            vec![
+                Action::Event(DroneEvent::PacketDropped(create_packet(
+                    fragment(3),
+                    2,
+                    vec![1, 11, 12]
+                ))),
+                Action::Send {
+                    to: 1,
+                    packet: create_nack(3, NackType::Dropped, vec![11, 1]),
+                },
+            ]
+        );
+
+        let mut core = create_core(&[1, 12], 0.0);
+        let actions = core.on_send_failed(
+            create_packet(fragment(3), 2, vec![1, 11, 12]),
+            12,
+            SendFailure::Disconnected,
+        );
+        assert_eq!(
+            actions,
+            vec![Action::Send {
+                to: 1,
+                packet: create_nack(3, NackType::ErrorInRouting(12), vec![11, 1]),
+            }]
+        );
+
+        // An ACK can't be NACKed, it's delivered through the controller
+        let ack = create_packet(
+            PacketType::Ack(Ack { fragment_index: 3 }),
+            2,
+            vec![1, 11, 12],
+        );
+        let actions = core.on_send_failed(ack.clone(), 12, SendFailure::Full);
+        assert_eq!(actions, vec![Action::Shortcut(ack)]);
+
+        // The NACK can't reach the client either
+        let actions = core.on_send_failed(
+            create_nack(3, NackType::ErrorInRouting(12), vec![11, 1]),
+            1,
+            SendFailure::Disconnected,
+        );
+        assert_eq!(
+            actions,
+            vec![Action::Shortcut(create_nack(
+                3,
+                NackType::ErrorInRouting(12),
+                vec![11, 1]
+            ))]
+        );
+        assert_eq!(core.stats().snapshot().congestion_drops, 1);
+    }
+
+    /// Check that a flood request is sent to the neighbors in order, and that the drone answers
+    /// as a dead end only once every branch failed
+    #[test]
+    fn run_core_flood_dead_end() {
+        let mut core = create_core(&[1, 13, 12], 0.0);
+        let request = FloodRequest {
+            flood_id: 7,
+            initiator_id: 1,
+            path_trace: vec![(1, NodeType::Client)],
+        };
+
+        let actions = core.on_packet(create_packet(PacketType::FloodRequest(request), 0, vec![]));
+        let targets: Vec<u8> = actions
+            .iter()
+            .map(|action| match action {
+                Action::Send { to, .. } => *to,
+                other => panic!("Expected a send, received {:?}", other),
+            })
+            .collect();
+        assert_eq!(targets, [12, 13]);
+
+        let mut failed = Vec::new();
+        for action in actions {
+            if let Action::Send { to, packet } = action {
+                failed.push(core.on_send_failed(packet, to, SendFailure::Disconnected));
+            }
+        }
+        assert!(failed[0].is_empty());
+        match &failed[1][..] {
+            [Action::Send { to: 1, packet }] => match &packet.pack_type {
+                PacketType::FloodResponse(response) => {
+                    assert_eq!(response.flood_id, 7);
+                    assert_eq!(
+                        response.path_trace,
+                        [(1, NodeType::Client), (11, NodeType::Drone)]
+                    );
+                }
+                other => panic!("Expected a flood response, received {:?}", other),
+            },
+            other => panic!("Expected a single send, received {:?}", other),
+        }
+    }
+
+    /// Check that a flood request without path trace is discarded instead of panicking, when it
+    /// doesn't go through the validation of `on_packet`
+    #[test]
+    fn run_core_flood_empty_trace() {
+        let mut core = create_core(&[1, 12], 0.0);
+        let request = FloodRequest {
+            flood_id: 7,
+            initiator_id: 1,
+            path_trace: vec![],
+        };
+
+        let header = SourceRoutingHeader {
+            hop_index: 0,
+            hops: vec![],
+        };
+        core.handle_flood_req(request, 1, header);
+        assert!(core.take_actions().is_empty());
+    }
+
+    /// Check that the neighbors and the crash state follow the commands
+    #[test]
+    fn run_core_commands() {
+        let mut core = create_core(&[1], 0.0);
+
+        let actions = core.on_packet(create_fragment(3, vec![1, 11, 12]));
+        assert_eq!(
+            actions,
+            vec![Action::Send {
+                to: 1,
+                packet: create_nack(3, NackType::ErrorInRouting(12), vec![11, 1]),
+            }]
+        );
+
+        assert!(core
+            .on_command(DroneCommand::AddSender(12, unbounded().0))
+            .is_empty());
+        let actions = core.on_packet(create_fragment(3, vec![1, 11, 12]));
+        assert!(matches!(actions[..], [Action::Send { to: 12, .. }]));
+
+        core.on_command(DroneCommand::Crash);
+        assert!(core.is_crashed());
+        let actions = core.on_packet(create_fragment(3, vec![1, 11, 12]));
+        assert_eq!(
+            actions,
+            vec![Action::Send {
+                to: 1,
+                packet: create_nack(3, NackType::ErrorInRouting(11), vec![11, 1]),
+            }]
+        );
+    }
+}
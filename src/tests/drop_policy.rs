@@ -0,0 +1,175 @@
+/// Run tests on the drop policies
+#[cfg(test)]
+mod drop_policy_tests {
+    use crate::drop_policy::{Bernoulli, DropPolicy, EveryNth, GilbertElliott, Scripted};
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::{select, unbounded};
+    use rand::rngs::StdRng;
+    use rand::RngCore;
+    use rand::SeedableRng;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use wg_2024::controller::DroneCommand;
+    use wg_2024::drone::Drone;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, Packet, PacketType};
+
+    /// Ask the policy about `count` fragments and return the positions of the dropped ones
+    fn dropped_positions(policy: &mut dyn DropPolicy, count: u64) -> Vec<u64> {
+        let mut rng = StdRng::seed_from_u64(0);
+        (0..count)
+            .filter(|_| policy.should_drop(&mut rng))
+            .collect()
+    }
+
+    #[test]
+    fn run_bernoulli_rate() {
+        let mut policy = Bernoulli::new(0.0);
+        assert!(dropped_positions(&mut policy, 100).is_empty());
+
+        policy.set_rate(1.0);
+        assert_eq!(dropped_positions(&mut policy, 100).len(), 100);
+    }
+
+    #[test]
+    fn run_every_nth() {
+        let mut policy = EveryNth::new(3);
+        assert_eq!(dropped_positions(&mut policy, 10), vec![2, 5, 8]);
+
+        // A rate of 0.5 drops every other fragment
+        let mut policy = EveryNth::new(0);
+        assert!(dropped_positions(&mut policy, 10).is_empty());
+        policy.set_rate(0.5);
+        assert_eq!(dropped_positions(&mut policy, 6), vec![1, 3, 5]);
+    }
+
+    /// Check that a rate that isn't the inverse of an integer is kept, instead of being rounded
+    /// to the closest period
+    #[test]
+    fn run_every_nth_fractional_rate() {
+        let mut policy = EveryNth::new(0);
+        policy.set_rate(0.75);
+        assert_eq!(dropped_positions(&mut policy, 8), vec![1, 2, 3, 5, 6, 7]);
+        assert_eq!(dropped_positions(&mut policy, 1000).len(), 750);
+
+        policy.set_rate(0.4);
+        assert_eq!(dropped_positions(&mut policy, 1000).len(), 400);
+
+        policy.set_rate(0.8);
+        assert_eq!(dropped_positions(&mut policy, 1000).len(), 800);
+    }
+
+    #[test]
+    fn run_scripted() {
+        let mut policy = Scripted::new([0, 4, 5]);
+        policy.set_rate(1.0);
+
+        assert_eq!(dropped_positions(&mut policy, 10), vec![0, 4, 5]);
+    }
+
+    /// Check that losses happen in bursts: once in the bad state, every fragment is dropped
+    #[test]
+    fn run_gilbert_elliott_burst() {
+        let mut policy = GilbertElliott::new(0.0, 1.0, 1.0, 0.0);
+
+        assert_eq!(dropped_positions(&mut policy, 5), vec![0, 1, 2, 3, 4]);
+        assert!(policy.in_bad_state());
+
+        // Never leaving the good state, only the base rate applies
+        let mut policy = GilbertElliott::new(0.0, 1.0, 0.0, 1.0);
+        assert!(dropped_positions(&mut policy, 100).is_empty());
+        policy.set_rate(1.0);
+        assert_eq!(dropped_positions(&mut policy, 100).len(), 100);
+    }
+
+    /// Check that the drone consults the policy, and that SetPacketDropRate updates its rate
+    #[test]
+    fn run_drone_policy() {
+        let (c_send, c_recv) = unbounded();
+        let (s_send, s_recv) = unbounded();
+        let (d1_send, d1_recv) = unbounded();
+        let (d_command_send, d_command_recv) = unbounded();
+
+        let neighbours = HashMap::from([(1, c_send), (21, s_send)]);
+
+        let mut drone =
+            RustafarianDroneBuilder::new(11, unbounded().0, d_command_recv, d1_recv, neighbours)
+                .drop_policy(EveryNth::new(2))
+                .log_level(LogLevel::NONE)
+                .build();
+
+        thread::spawn(move || {
+            drone.run();
+        });
+
+        let mut send_fragments = |count: u64| -> Vec<u64> {
+            let mut dropped = vec![];
+            for fragment_index in 0..count {
+                d1_send
+                    .send(Packet {
+                        pack_type: PacketType::MsgFragment(Fragment {
+                            fragment_index,
+                            total_n_fragments: count,
+                            length: 128,
+                            data: [2; 128],
+                        }),
+                        routing_header: SourceRoutingHeader {
+                            hop_index: 1,
+                            hops: vec![1, 11, 21],
+                        },
+                        session_id: 1,
+                    })
+                    .unwrap();
+
+                select! {
+                    recv(c_recv) -> _nack => dropped.push(fragment_index),
+                    recv(s_recv) -> _fragment => {},
+                }
+            }
+            dropped
+        };
+
+        assert_eq!(send_fragments(4), vec![1, 3]);
+
+        d_command_send
+            .send(DroneCommand::SetPacketDropRate(0.25))
+            .unwrap();
+        assert_eq!(send_fragments(8), vec![3, 7]);
+    }
+
+    /// Policy that never drops, remembering the rates it receives
+    struct RecordRates(Arc<Mutex<Vec<f32>>>);
+
+    impl DropPolicy for RecordRates {
+        fn should_drop(&mut self, _rng: &mut dyn RngCore) -> bool {
+            false
+        }
+
+        fn set_rate(&mut self, pdr: f32) {
+            self.0.lock().unwrap().push(pdr);
+        }
+    }
+
+    /// Check that SetPacketDropRate saturates the rate, as the builder does
+    #[test]
+    fn run_drone_policy_saturated_rate() {
+        let rates = Arc::new(Mutex::new(vec![]));
+        let mut core = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            unbounded().1,
+            HashMap::new(),
+        )
+        .drop_policy(RecordRates(rates.clone()))
+        .log_level(LogLevel::NONE)
+        .build_core();
+
+        for pdr in [1.5, -0.5, f32::NAN, 0.3] {
+            core.on_command(DroneCommand::SetPacketDropRate(pdr));
+        }
+
+        assert_eq!(*rates.lock().unwrap(), vec![1.0, 0.0, 0.0, 0.3]);
+    }
+}
@@ -0,0 +1,144 @@
+/// Run tests on the controller events of the packets sent by the drone
+#[cfg(test)]
+mod egress_tests {
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::{unbounded, Receiver};
+    use std::collections::HashMap;
+    use wg_2024::controller::DroneEvent;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{FloodRequest, Fragment, NackType, NodeType, Packet, PacketType};
+
+    fn create_flood_request(flood_id: u64) -> Packet {
+        Packet {
+            pack_type: PacketType::FloodRequest(FloodRequest {
+                flood_id,
+                initiator_id: 1,
+                path_trace: vec![(1, NodeType::Client)],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 0,
+                hops: vec![],
+            },
+            session_id: 1,
+        }
+    }
+
+    fn sent_packets(controller_recv: &Receiver<DroneEvent>) -> Vec<Packet> {
+        controller_recv
+            .try_iter()
+            .filter_map(|event| match event {
+                DroneEvent::PacketSent(packet) => Some(packet),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Check that forwarded flood requests and flood responses are reported to the controller
+    #[test]
+    fn run_egress_flood_events() {
+        let (c_send, c_recv) = unbounded();
+        let (d2_send, d2_recv) = unbounded();
+        let (d3_send, d3_recv) = unbounded();
+        let (controller_send, controller_recv) = unbounded();
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            controller_send,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send), (12, d2_send), (13, d3_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .build();
+
+        drone.handle_packet(create_flood_request(1));
+        let sent = sent_packets(&controller_recv);
+        assert_eq!(sent.len(), 2);
+        assert!(sent.contains(&d2_recv.try_recv().unwrap()));
+        assert!(sent.contains(&d3_recv.try_recv().unwrap()));
+
+        // Duplicate request, answered with a response
+        drone.handle_packet(create_flood_request(1));
+        let sent = sent_packets(&controller_recv);
+        assert_eq!(sent, vec![c_recv.try_recv().unwrap()]);
+        assert!(matches!(sent[0].pack_type, PacketType::FloodResponse(_)));
+    }
+
+    /// Check that a flood response that can't reach the sender goes through the controller
+    #[test]
+    fn run_egress_flood_response_shortcut() {
+        let (c_send, c_recv) = unbounded::<Packet>();
+        let (controller_send, controller_recv) = unbounded();
+        drop(c_recv);
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            controller_send,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .build();
+
+        // Dead end, the response is sent back to the client
+        drone.handle_packet(create_flood_request(1));
+
+        match controller_recv.try_recv().unwrap() {
+            DroneEvent::ControllerShortcut(packet) => {
+                assert_eq!(packet.routing_header.hops, vec![11, 1]);
+                assert!(matches!(packet.pack_type, PacketType::FloodResponse(_)));
+            }
+            other => panic!("Expected a shortcut, received {:?}", other),
+        }
+        assert_eq!(drone.stats().snapshot().shortcuts, 1);
+    }
+
+    /// Check that a NACK that can't be sent back goes through the controller, instead of the
+    /// fragment it refers to
+    #[test]
+    fn run_egress_nack_shortcut() {
+        let (c_send, c_recv) = unbounded::<Packet>();
+        let (controller_send, controller_recv) = unbounded();
+        drop(c_recv);
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            controller_send,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .build();
+
+        // 21 is not a neighbor
+        drone.handle_packet(Packet {
+            pack_type: PacketType::MsgFragment(Fragment {
+                fragment_index: 2,
+                total_n_fragments: 3,
+                length: 128,
+                data: [1; 128],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 21],
+            },
+            session_id: 1,
+        });
+
+        match controller_recv.try_recv().unwrap() {
+            DroneEvent::ControllerShortcut(packet) => {
+                assert_eq!(packet.routing_header.hops, vec![11, 1]);
+                match packet.pack_type {
+                    PacketType::Nack(nack) => {
+                        assert_eq!(nack.nack_type, NackType::ErrorInRouting(21));
+                        assert_eq!(nack.fragment_index, 2);
+                    }
+                    other => panic!("Expected a NACK, received {:?}", other),
+                }
+            }
+            other => panic!("Expected a shortcut, received {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Packets shared by the tests
+#![cfg(test)]
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{FloodRequest, Fragment, Nack, NackType, NodeType, Packet, PacketType};
+
+/// Create a packet of session 1
+pub fn create_packet(pack_type: PacketType, hop_index: usize, hops: Vec<NodeId>) -> Packet {
+    Packet {
+        pack_type,
+        routing_header: SourceRoutingHeader { hop_index, hops },
+        session_id: 1,
+    }
+}
+
+/// A fragment of a message split in 10 fragments
+pub fn fragment(fragment_index: u64) -> PacketType {
+    PacketType::MsgFragment(Fragment {
+        fragment_index,
+        total_n_fragments: 10,
+        length: 128,
+        data: [0; 128],
+    })
+}
+
+/// Create a fragment received by the second node of the route
+pub fn create_fragment(fragment_index: u64, hops: Vec<NodeId>) -> Packet {
+    create_packet(fragment(fragment_index), 1, hops)
+}
+
+/// Create a NACK received by the second node of the route
+pub fn create_nack(fragment_index: u64, nack_type: NackType, hops: Vec<NodeId>) -> Packet {
+    create_packet(
+        PacketType::Nack(Nack {
+            fragment_index,
+            nack_type,
+        }),
+        1,
+        hops,
+    )
+}
+
+/// Create a flood request started by client 1, in the session with the same ID as the flood
+pub fn create_flood_request(flood_id: u64) -> Packet {
+    Packet {
+        pack_type: PacketType::FloodRequest(FloodRequest {
+            flood_id,
+            initiator_id: 1,
+            path_trace: vec![(1, NodeType::Client)],
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 0,
+            hops: vec![],
+        },
+        session_id: flood_id,
+    }
+}
@@ -0,0 +1,96 @@
+/// Run tests on the bounded flood cache
+#[cfg(test)]
+mod flood_cache_tests {
+    use crate::flood_cache::{FloodCache, FloodCacheConfig};
+    use std::time::{Duration, Instant};
+
+    /// Check that each initiator has its own window, and that duplicates are found inside it
+    #[test]
+    fn run_flood_cache_window() {
+        let now = Instant::now();
+        let mut cache = FloodCache::new(FloodCacheConfig {
+            window: 3,
+            ..FloodCacheConfig::default()
+        });
+
+        for flood_id in 0..10 {
+            cache.insert(1, flood_id, now);
+        }
+        cache.insert(2, 0, now);
+
+        assert_eq!(cache.len(), 4);
+        // Only the last 3 floods of initiator 1 are remembered
+        assert!(!cache.contains(1, 6, now));
+        for flood_id in 7..10 {
+            assert!(cache.contains(1, flood_id, now));
+        }
+        // Initiator 1 didn't push out the flood of initiator 2
+        assert!(cache.contains(2, 0, now));
+        assert!(!cache.contains(3, 0, now));
+
+        // A duplicate doesn't move the window
+        cache.insert(1, 7, now);
+        assert!(cache.contains(1, 7, now));
+        assert_eq!(cache.len(), 4);
+    }
+
+    /// Check that the total capacity forgets the oldest flood, whatever its initiator
+    #[test]
+    fn run_flood_cache_capacity() {
+        let now = Instant::now();
+        let mut cache = FloodCache::new(FloodCacheConfig {
+            capacity: Some(2),
+            ..FloodCacheConfig::default()
+        });
+
+        cache.insert(1, 0, now);
+        cache.insert(2, 0, now);
+        cache.insert(3, 0, now);
+
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(1, 0, now));
+        assert!(cache.contains(2, 0, now));
+        assert!(cache.contains(3, 0, now));
+    }
+
+    /// Check that a window or capacity of 0 still suppresses the duplicates of the last flood
+    #[test]
+    fn run_flood_cache_zero_limits() {
+        let now = Instant::now();
+        for config in [
+            FloodCacheConfig {
+                window: 0,
+                ..FloodCacheConfig::default()
+            },
+            FloodCacheConfig {
+                capacity: Some(0),
+                ..FloodCacheConfig::default()
+            },
+        ] {
+            let mut cache = FloodCache::new(config);
+
+            cache.insert(1, 0, now);
+            assert!(cache.contains(1, 0, now), "{:?}", config);
+            assert_eq!(cache.len(), 1);
+        }
+    }
+
+    /// Check that flood requests are forgotten after the TTL
+    #[test]
+    fn run_flood_cache_ttl() {
+        let start = Instant::now();
+        let mut cache = FloodCache::new(FloodCacheConfig {
+            ttl: Some(Duration::from_secs(10)),
+            ..FloodCacheConfig::default()
+        });
+
+        cache.insert(1, 0, start);
+        cache.insert(1, 1, start + Duration::from_secs(5));
+
+        assert!(cache.contains(1, 0, start + Duration::from_secs(9)));
+        assert!(!cache.contains(1, 0, start + Duration::from_secs(10)));
+        assert!(cache.contains(1, 1, start + Duration::from_secs(10)));
+        assert!(!cache.contains(1, 1, start + Duration::from_secs(15)));
+        assert!(cache.is_empty());
+    }
+}
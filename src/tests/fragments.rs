@@ -426,11 +426,12 @@ mod fragment_tests {
 
         d1_send.send(msg).unwrap();
 
+        // The NACK goes back to the node that actually sent the fragment
         let expected_nack = Packet {
             session_id: 0,
             routing_header: SourceRoutingHeader {
-                hop_index: 3,
-                hops: [11, 13, 12, 1].to_vec(),
+                hop_index: 1,
+                hops: [11, 1].to_vec(),
             },
             pack_type: PacketType::Nack(Nack {
                 fragment_index: 0,
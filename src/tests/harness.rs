@@ -0,0 +1,99 @@
+/// Run tests on the harness used to build networks of drones
+#[cfg(test)]
+mod harness_tests {
+    use crate::harness::{Topology, CLIENT, SERVER};
+    use crate::tests::fixtures::create_fragment;
+    use std::time::Duration;
+    use wg_2024::controller::DroneEvent;
+    use wg_2024::packet::{NackType, PacketType};
+
+    /// Check the links of the predefined topologies
+    #[test]
+    fn run_harness_topologies() {
+        let line = Topology::line(3);
+        assert_eq!(line.neighbors(CLIENT), [11]);
+        assert_eq!(line.neighbors(12), [11, 13]);
+        assert_eq!(line.neighbors(SERVER), [13]);
+
+        let ring = Topology::ring(4);
+        assert_eq!(ring.neighbors(11), [12, 14, CLIENT]);
+        assert_eq!(ring.neighbors(SERVER), [13]);
+
+        let star = Topology::star(3);
+        assert_eq!(star.neighbors(11), [12, 13, 14]);
+        assert_eq!(star.neighbors(12), [11, CLIENT]);
+        assert_eq!(star.neighbors(SERVER), [14]);
+
+        // 11 12 13
+        // 14 15 16
+        let mesh = Topology::mesh(2, 3);
+        assert_eq!(mesh.neighbors(11), [14, 12, CLIENT]);
+        assert_eq!(mesh.neighbors(15), [12, 14, 16]);
+        assert_eq!(mesh.neighbors(SERVER), [16]);
+
+        //       11
+        //   12      13
+        // 14  15  16  17
+        let tree = Topology::tree(2, 2);
+        assert_eq!(tree.config().drone.len(), 7);
+        assert_eq!(tree.neighbors(11), [12, 13, CLIENT]);
+        assert_eq!(tree.neighbors(13), [11, 16, 17]);
+        assert_eq!(tree.neighbors(SERVER), [17]);
+
+        let custom = Topology::new()
+            .drone(30)
+            .drone(31)
+            .client(5)
+            .link(30, 31)
+            .link(5, 30)
+            .link(5, 31)
+            .pdr(0.5);
+        assert_eq!(custom.neighbors(5), [30, 31]);
+        assert!(custom.config().drone.iter().all(|drone| drone.pdr == 0.5));
+    }
+
+    /// Check that a fragment crosses a star from the client to the server, and that the events of
+    /// every drone are collected
+    #[test]
+    fn run_harness_delivery() {
+        let harness = Topology::star(3).start();
+
+        let hops = vec![CLIENT, 12, 11, 14, SERVER];
+        harness.inject(create_fragment(0, hops.clone()));
+
+        let delivered = harness
+            .recv_default(SERVER)
+            .expect("Fragment not delivered");
+        assert_eq!(delivered.routing_header.hop_index, 4);
+        assert_eq!(delivered.routing_header.hops, hops);
+        assert!(harness.delivered(CLIENT).is_empty());
+
+        let sent = (0..3)
+            .filter_map(|_| harness.recv_event(Duration::from_secs(1)))
+            .filter(|event| matches!(event, DroneEvent::PacketSent(_)))
+            .count();
+        assert_eq!(sent, 3);
+        assert_eq!(harness.stats(11).sent["MsgFragment"], 1);
+
+        harness.stop();
+    }
+
+    /// Check that a crashed drone is removed from its neighbors, so that the fragments routed
+    /// through it are NACKed
+    #[test]
+    fn run_harness_crash() {
+        let mut harness = Topology::line(3).start();
+
+        harness.crash(12);
+        harness.inject(create_fragment(0, vec![CLIENT, 11, 12, 13, SERVER]));
+
+        let nack = harness.recv_default(CLIENT).expect("NACK not received");
+        match nack.pack_type {
+            PacketType::Nack(nack) => assert_eq!(nack.nack_type, NackType::ErrorInRouting(12)),
+            other => panic!("Expected a NACK, received {:?}", other),
+        }
+        assert!(harness.delivered(SERVER).is_empty());
+
+        harness.stop();
+    }
+}
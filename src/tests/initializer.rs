@@ -0,0 +1,235 @@
+/// Run tests on the network initializer
+#[cfg(test)]
+mod initializer_tests {
+    use crate::drop_policy::EveryNth;
+    use crate::initializer::{load_config, parse_config, start_network_with, ConfigError};
+    use crate::LogLevel;
+    use std::time::Duration;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, NackType, Packet, PacketType};
+
+    const LINE_CONFIG: &str = r#"
+        [[drone]]
+        id = 11
+        connected_node_ids = [1, 12, 21]
+        pdr = 0.0
+
+        [[drone]]
+        id = 12
+        connected_node_ids = [11, 21]
+        pdr = 0.0
+
+        [[client]]
+        id = 1
+        connected_drone_ids = [11]
+
+        [[server]]
+        id = 21
+        connected_drone_ids = [11, 12]
+    "#;
+
+    /// Check that a network started from a configuration delivers a fragment from client to server
+    #[test]
+    fn run_line_network() {
+        let config = parse_config(LINE_CONFIG).unwrap();
+        let network = start_network_with(&config, |builder| builder.log_level(LogLevel::NONE));
+
+        assert_eq!(network.drone_commands.len(), 2);
+        assert_eq!(network.handles.len(), 2);
+
+        let mut msg = Packet {
+            pack_type: PacketType::MsgFragment(Fragment {
+                fragment_index: 0,
+                total_n_fragments: 1,
+                length: 128,
+                data: [2; 128],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 12, 21],
+            },
+            session_id: 1,
+        };
+
+        network.clients[&1].neighbors[&11]
+            .send(msg.clone())
+            .unwrap();
+        msg.routing_header.hop_index = 3;
+
+        assert_eq!(network.servers[&21].packet_recv.recv().unwrap(), msg);
+    }
+
+    /// Check that a drop policy set through `configure` keeps its rate, instead of being
+    /// overwritten by the PDR of the configuration
+    #[test]
+    fn run_configured_drop_policy() {
+        let config = parse_config(LINE_CONFIG).unwrap();
+        let network = start_network_with(&config, |builder| {
+            let builder = builder.log_level(LogLevel::NONE);
+            if builder.id() == 11 {
+                builder.drop_policy(EveryNth::new(2))
+            } else {
+                builder
+            }
+        });
+
+        for fragment_index in 0..4 {
+            let fragment = Packet {
+                pack_type: PacketType::MsgFragment(Fragment {
+                    fragment_index,
+                    total_n_fragments: 4,
+                    length: 128,
+                    data: [2; 128],
+                }),
+                routing_header: SourceRoutingHeader {
+                    hop_index: 1,
+                    hops: vec![1, 11, 12, 21],
+                },
+                session_id: 1,
+            };
+            network.clients[&1].neighbors[&11].send(fragment).unwrap();
+        }
+
+        let timeout = Duration::from_secs(1);
+        let delivered: Vec<u64> = (0..2)
+            .map(
+                |_| match network.servers[&21].packet_recv.recv_timeout(timeout) {
+                    Ok(Packet {
+                        pack_type: PacketType::MsgFragment(fragment),
+                        ..
+                    }) => fragment.fragment_index,
+                    other => panic!("Expected a fragment, received {:?}", other),
+                },
+            )
+            .collect();
+        assert_eq!(delivered, [0, 2]);
+
+        for _ in 0..2 {
+            match network.clients[&1].packet_recv.recv_timeout(timeout) {
+                Ok(Packet {
+                    pack_type: PacketType::Nack(nack),
+                    ..
+                }) => assert_eq!(nack.nack_type, NackType::Dropped),
+                other => panic!("Expected a NACK, received {:?}", other),
+            }
+        }
+    }
+
+    /// Check that a connection to a node that doesn't exist is rejected
+    #[test]
+    fn run_unknown_node() {
+        let config = LINE_CONFIG.replace("[11, 21]", "[11, 22]");
+
+        match parse_config(&config) {
+            Err(ConfigError::UnknownNode(12, 22)) => {}
+            other => panic!("Expected an unknown node error, got {:?}", other.err()),
+        }
+    }
+
+    /// Check that two nodes with the same ID are rejected
+    #[test]
+    fn run_duplicate_node() {
+        let config = LINE_CONFIG.replace("id = 21", "id = 1");
+
+        match parse_config(&config) {
+            Err(ConfigError::DuplicateNode(1)) => {}
+            other => panic!("Expected a duplicate node error, got {:?}", other.err()),
+        }
+    }
+
+    /// Parse a configuration that must be rejected
+    fn config_error(config: &str) -> ConfigError {
+        match parse_config(config) {
+            Err(error) => error,
+            Ok(_) => panic!("Expected the configuration to be rejected"),
+        }
+    }
+
+    /// Check that the sample configuration shipped with the crate is valid
+    #[test]
+    fn run_sample_config() {
+        let config = load_config("config/sample.toml").unwrap();
+        assert_eq!(config.drone.len(), 4);
+    }
+
+    /// Check that a node connected to itself is rejected
+    #[test]
+    fn run_self_link() {
+        let error = config_error(&LINE_CONFIG.replace("[11, 21]", "[11, 12, 21]"));
+        assert!(matches!(error, ConfigError::SelfLink(12)), "{:?}", error);
+    }
+
+    /// Check that a connection listed by only one of the two nodes is rejected
+    #[test]
+    fn run_one_sided_link() {
+        let error = config_error(&LINE_CONFIG.replace("[11, 21]", "[21]"));
+        assert!(
+            matches!(error, ConfigError::OneSidedLink(11, 12)),
+            "{:?}",
+            error
+        );
+    }
+
+    /// Check that clients and servers can only be connected to drones
+    #[test]
+    fn run_endpoint_link() {
+        let config = LINE_CONFIG
+            .replace(
+                "connected_drone_ids = [11]",
+                "connected_drone_ids = [11, 21]",
+            )
+            .replace("[11, 12]", "[1, 11, 12]");
+        let error = config_error(&config);
+        assert!(
+            matches!(error, ConfigError::EndpointLink(1, 21)),
+            "{:?}",
+            error
+        );
+    }
+
+    /// Check that a client must be connected to one or two drones
+    #[test]
+    fn run_client_links() {
+        let config = LINE_CONFIG
+            .replace("connected_drone_ids = [11]", "connected_drone_ids = []")
+            .replace("[1, 12, 21]", "[12, 21]");
+        let error = config_error(&config);
+        assert!(
+            matches!(error, ConfigError::ClientLinks(1, 0)),
+            "{:?}",
+            error
+        );
+    }
+
+    /// Check that a server must be connected to at least two drones
+    #[test]
+    fn run_server_links() {
+        let config = LINE_CONFIG
+            .replace("[11, 12]", "[12]")
+            .replace("[1, 12, 21]", "[1, 12]");
+        let error = config_error(&config);
+        assert!(
+            matches!(error, ConfigError::ServerLinks(21, 1)),
+            "{:?}",
+            error
+        );
+    }
+
+    /// Check that a PDR outside `0.0..=1.0` is rejected
+    #[test]
+    fn run_invalid_pdr() {
+        let error = config_error(&LINE_CONFIG.replacen("pdr = 0.0", "pdr = 1.5", 1));
+        assert!(
+            matches!(error, ConfigError::InvalidPdr(11, pdr) if pdr == 1.5),
+            "{:?}",
+            error
+        );
+
+        let error = config_error(&LINE_CONFIG.replacen("pdr = 0.0", "pdr = -0.1", 1));
+        assert!(
+            matches!(error, ConfigError::InvalidPdr(11, _)),
+            "{:?}",
+            error
+        );
+    }
+}
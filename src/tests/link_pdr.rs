@@ -0,0 +1,112 @@
+/// Run tests on the per-link drop rates
+#[cfg(test)]
+mod link_pdr_tests {
+    use crate::{LogLevel, RustafarianCommand, RustafarianDroneBuilder};
+    use crossbeam_channel::{unbounded, Receiver, Sender};
+    use std::collections::HashMap;
+    use std::thread;
+    use wg_2024::controller::DroneCommand;
+    use wg_2024::drone::Drone;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, NackType, Packet, PacketType};
+
+    fn create_fragment(destination: u8) -> Packet {
+        Packet {
+            pack_type: PacketType::MsgFragment(Fragment {
+                fragment_index: 0,
+                total_n_fragments: 1,
+                length: 128,
+                data: [2; 128],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, destination],
+            },
+            session_id: 1,
+        }
+    }
+
+    /// Check whether the client receives a Dropped NACK, or the destination receives the fragment
+    fn is_dropped(c_recv: &Receiver<Packet>, destination_recv: &Receiver<Packet>) -> bool {
+        crossbeam_channel::select! {
+            recv(c_recv) -> nack => match nack.unwrap().pack_type {
+                PacketType::Nack(nack) => nack.nack_type == NackType::Dropped,
+                other => panic!("Expected a NACK, received {:?}", other),
+            },
+            recv(destination_recv) -> _fragment => false,
+        }
+    }
+
+    /// Create drone 11, connected to client 1 and to the nodes 12 and 21
+    fn create_drone(
+        configure: impl Fn(RustafarianDroneBuilder) -> RustafarianDroneBuilder,
+    ) -> (
+        Sender<Packet>,
+        Receiver<Packet>,
+        Receiver<Packet>,
+        Receiver<Packet>,
+    ) {
+        let (c_send, c_recv) = unbounded();
+        let (d2_send, d2_recv) = unbounded();
+        let (s_send, s_recv) = unbounded();
+        let (d1_send, d1_recv) = unbounded();
+        let (d_command_send, d_command_recv) = unbounded::<DroneCommand>();
+
+        let neighbours = HashMap::from([(1, c_send), (12, d2_send), (21, s_send)]);
+
+        let builder =
+            RustafarianDroneBuilder::new(11, unbounded().0, d_command_recv, d1_recv, neighbours)
+                .log_level(LogLevel::NONE);
+        let mut drone = configure(builder).build();
+
+        thread::spawn(move || {
+            // Keep the command channel open while the drone is running
+            let _d_command_send = d_command_send;
+            drone.run();
+        });
+
+        (d1_send, c_recv, d2_recv, s_recv)
+    }
+
+    /// Check that a link drop rate set at construction only applies to that link
+    #[test]
+    fn run_link_pdr_builder() {
+        let (d1_send, c_recv, d2_recv, s_recv) =
+            create_drone(|builder| builder.pdr(0.0).link_pdr(21, 1.0));
+
+        for _ in 0..10 {
+            d1_send.send(create_fragment(21)).unwrap();
+            assert!(is_dropped(&c_recv, &s_recv));
+
+            d1_send.send(create_fragment(12)).unwrap();
+            assert!(!is_dropped(&c_recv, &d2_recv));
+        }
+    }
+
+    /// Check that link drop rates can be changed while the drone is running
+    #[test]
+    fn run_link_pdr_runtime() {
+        let (extension_send, extension_recv) = unbounded();
+        let (d1_send, c_recv, d2_recv, s_recv) =
+            create_drone(|builder| builder.pdr(1.0).extension_commands(extension_recv.clone()));
+
+        d1_send.send(create_fragment(12)).unwrap();
+        assert!(is_dropped(&c_recv, &d2_recv));
+
+        // The good link overrides the drone PDR
+        extension_send
+            .send(RustafarianCommand::SetLinkDropRate(12, 0.0))
+            .unwrap();
+        d1_send.send(create_fragment(12)).unwrap();
+        assert!(!is_dropped(&c_recv, &d2_recv));
+        d1_send.send(create_fragment(21)).unwrap();
+        assert!(is_dropped(&c_recv, &s_recv));
+
+        // Once cleared, the drone PDR is used again
+        extension_send
+            .send(RustafarianCommand::ClearLinkDropRate(12))
+            .unwrap();
+        d1_send.send(create_fragment(12)).unwrap();
+        assert!(is_dropped(&c_recv, &d2_recv));
+    }
+}
@@ -0,0 +1,92 @@
+/// Run tests on the log sinks
+#[cfg(test)]
+mod logging_tests {
+    use crate::logging::{FileSink, MemorySink};
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::unbounded;
+    use std::collections::HashMap;
+    use std::{env, fs};
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, Packet, PacketType};
+
+    /// Check that the memory sink keeps only the last lines allowed by the log level
+    #[test]
+    fn run_memory_sink() {
+        let sink = MemorySink::new(2);
+
+        let drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            unbounded().1,
+            HashMap::new(),
+        )
+        .log_level(LogLevel::INFO)
+        .log_sink(sink.clone())
+        .build();
+
+        drone.log("first", LogLevel::ERROR);
+        drone.log("second", LogLevel::INFO);
+        drone.log("filtered", LogLevel::DEBUG);
+        drone.log("third", LogLevel::INFO);
+
+        assert_eq!(
+            sink.lines(),
+            vec![
+                "[Rustafarian Drone 11] - LEVEL: INFO >>> second",
+                "[Rustafarian Drone 11] - LEVEL: INFO >>> third",
+            ]
+        );
+
+        sink.clear();
+        assert!(sink.lines().is_empty());
+    }
+
+    /// Check that every drone writes its logs to its own file
+    #[test]
+    fn run_file_sink() {
+        let dir = env::temp_dir().join(format!("rustafarian-logs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        for id in [11, 12] {
+            let (c_send, _c_recv) = unbounded();
+            let mut drone = RustafarianDroneBuilder::new(
+                id,
+                unbounded().0,
+                unbounded().1,
+                unbounded().1,
+                HashMap::from([(1, c_send)]),
+            )
+            .log_level(LogLevel::ERROR)
+            .log_sink(FileSink::in_dir(&dir, id).unwrap())
+            .build();
+
+            // Node 21 is not a neighbor, so the drone logs an error
+            drone.handle_packet(Packet {
+                pack_type: PacketType::MsgFragment(Fragment {
+                    fragment_index: 0,
+                    total_n_fragments: 1,
+                    length: 128,
+                    data: [2; 128],
+                }),
+                routing_header: SourceRoutingHeader {
+                    hop_index: 1,
+                    hops: vec![1, id, 21],
+                },
+                session_id: 1,
+            });
+        }
+
+        for id in [11, 12] {
+            let content = fs::read_to_string(dir.join(format!("drone-{}.log", id))).unwrap();
+            let lines: Vec<&str> = content.lines().collect();
+
+            assert!(!lines.is_empty());
+            for line in lines {
+                assert!(line.starts_with(&format!("[Rustafarian Drone {}] - LEVEL: ERROR", id)));
+            }
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
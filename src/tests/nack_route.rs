@@ -0,0 +1,85 @@
+/// Run tests on the route of the NACKs sent back by the drone
+#[cfg(test)]
+mod nack_route_tests {
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::{unbounded, Receiver};
+    use std::collections::HashMap;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, NackType, Packet, PacketType};
+
+    fn create_fragment(hop_index: usize, hops: Vec<u8>) -> Packet {
+        Packet {
+            pack_type: PacketType::MsgFragment(Fragment {
+                fragment_index: 1,
+                total_n_fragments: 2,
+                length: 128,
+                data: [1; 128],
+            }),
+            routing_header: SourceRoutingHeader { hop_index, hops },
+            session_id: 1,
+        }
+    }
+
+    /// Check that the next packet on the channel is a NACK of the given type, with the given route
+    fn expect_nack(recv: &Receiver<Packet>, nack_type: NackType, hops: Vec<u8>) {
+        let packet = recv.try_recv().expect("No NACK received");
+        assert_eq!(
+            packet.routing_header,
+            SourceRoutingHeader { hop_index: 1, hops }
+        );
+        match packet.pack_type {
+            PacketType::Nack(nack) => assert_eq!(nack.nack_type, nack_type),
+            other => panic!("Expected a NACK, received {:?}", other),
+        }
+    }
+
+    /// Send the fragment to drone 11, connected to client 1 and to drone 12
+    fn send_to_drone(packet: Packet, pdr: f32) -> (Receiver<Packet>, Receiver<Packet>) {
+        let (c_send, c_recv) = unbounded();
+        let (d2_send, d2_recv) = unbounded();
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send), (12, d2_send)]),
+        )
+        .pdr(pdr)
+        .log_level(LogLevel::NONE)
+        .build();
+        drone.handle_packet(packet);
+
+        (c_recv, d2_recv)
+    }
+
+    /// The drone is not in the routing header: the NACK goes back to the previous hop
+    #[test]
+    fn run_nack_route_unexpected_recipient() {
+        let (c_recv, _d2_recv) = send_to_drone(create_fragment(1, vec![1, 13, 21]), 0.0);
+        expect_nack(&c_recv, NackType::UnexpectedRecipient(11), vec![11, 1]);
+    }
+
+    /// The drone appears twice in the route: the NACK follows the hops actually traversed
+    #[test]
+    fn run_nack_route_repeated_ids() {
+        let (c_recv, d2_recv) = send_to_drone(create_fragment(3, vec![1, 11, 12, 11, 21]), 0.0);
+        expect_nack(&d2_recv, NackType::ErrorInRouting(21), vec![11, 12, 11, 1]);
+        assert!(c_recv.try_recv().is_err());
+    }
+
+    /// A dropped fragment is NACKed with the route up to the drone
+    #[test]
+    fn run_nack_route_dropped() {
+        let (c_recv, d2_recv) = send_to_drone(create_fragment(1, vec![1, 11, 12]), 1.0);
+        expect_nack(&c_recv, NackType::Dropped, vec![11, 1]);
+        assert!(d2_recv.try_recv().is_err());
+    }
+
+    /// With `hop_index` 0 the previous hop is unknown, so the NACK goes towards the sender
+    #[test]
+    fn run_nack_route_zero_hop_index() {
+        let (c_recv, _d2_recv) = send_to_drone(create_fragment(0, vec![1, 11, 12]), 0.0);
+        expect_nack(&c_recv, NackType::UnexpectedRecipient(11), vec![11, 1]);
+    }
+}
@@ -0,0 +1,172 @@
+/// Run tests on the priority scheduling of control packets
+#[cfg(test)]
+mod priority_tests {
+    use crate::stats::DroneStats;
+    use crate::{packet_type_name, LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::unbounded;
+    use std::collections::HashMap;
+    use wg_2024::controller::DroneCommand;
+    use wg_2024::drone::Drone;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, Fragment, NackType, Packet, PacketType};
+
+    fn create_packet(pack_type: PacketType) -> Packet {
+        Packet {
+            pack_type,
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 12],
+            },
+            session_id: 1,
+        }
+    }
+
+    fn create_fragment(fragment_index: u64) -> Packet {
+        create_packet(PacketType::MsgFragment(Fragment {
+            fragment_index,
+            total_n_fragments: 10,
+            length: 128,
+            data: [1; 128],
+        }))
+    }
+
+    fn create_ack(fragment_index: u64) -> Packet {
+        create_packet(PacketType::Ack(Ack { fragment_index }))
+    }
+
+    /// Run drone 11 on the current thread, with the given packets already in its channel, until
+    /// every packet is forwarded to drone 12.
+    ///
+    /// ### Returns
+    /// The types of the packets received by drone 12, in order, and the stats of the drone
+    fn run_drone(
+        control_burst: Option<usize>,
+        packets: Vec<Packet>,
+    ) -> (Vec<&'static str>, DroneStats) {
+        let (d1_send, d1_recv) = unbounded();
+        let (d2_send, d2_recv) = unbounded();
+        for packet in packets {
+            d1_send.send(packet).unwrap();
+        }
+        drop(d1_send);
+
+        let mut builder = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            d1_recv,
+            HashMap::from([(12, d2_send)]),
+        )
+        .log_level(LogLevel::NONE);
+        if let Some(control_burst) = control_burst {
+            builder = builder.priority_scheduling(control_burst);
+        }
+        let mut drone = builder.build();
+        let stats = drone.stats();
+
+        // Both channels are closed, so the drone stops once the packets are handled
+        drone.run();
+
+        let types = d2_recv
+            .try_iter()
+            .map(|packet| packet_type_name(&packet.pack_type))
+            .collect();
+        (types, stats.snapshot())
+    }
+
+    /// Check that an ACK waiting behind fragments is forwarded first, and that without priority
+    /// scheduling the arrival order is kept
+    #[test]
+    fn run_priority_control_first() {
+        let mut packets: Vec<Packet> = (0..5).map(create_fragment).collect();
+        packets.push(create_ack(0));
+
+        let (types, stats) = run_drone(Some(8), packets.clone());
+        assert_eq!(
+            types,
+            [
+                "Ack",
+                "MsgFragment",
+                "MsgFragment",
+                "MsgFragment",
+                "MsgFragment",
+                "MsgFragment"
+            ]
+        );
+        assert_eq!(stats.prioritized, 1);
+        assert_eq!(stats.fair_turns, 0);
+        assert_eq!(stats.max_queued, 6);
+
+        let (types, stats) = run_drone(None, packets);
+        assert_eq!(types.last(), Some(&"Ack"));
+        assert_eq!(stats.prioritized, 0);
+        assert_eq!(stats.max_queued, 0);
+    }
+
+    /// Check that a fragment is forwarded after every `control_burst` control packets, so a
+    /// stream of ACKs can't starve the fragments
+    #[test]
+    fn run_priority_fairness() {
+        let mut packets: Vec<Packet> = (0..3).map(create_fragment).collect();
+        packets.extend((0..7).map(create_ack));
+
+        let (types, stats) = run_drone(Some(2), packets);
+        let order: String = types
+            .iter()
+            .map(|name| if *name == "Ack" { 'C' } else { 'F' })
+            .collect();
+        assert_eq!(order, "CCFCCFCCFC");
+        // The last ACK is handled when no fragment is waiting anymore
+        assert_eq!(stats.prioritized, 6);
+        assert_eq!(stats.fair_turns, 3);
+    }
+
+    /// Check that the packets waiting in the inbox are still handled after a crash, fragments
+    /// being NACKed
+    #[test]
+    fn run_priority_crash() {
+        let (d_command_send, d_command_recv) = unbounded();
+        let (d1_send, d1_recv) = unbounded();
+        let (c_send, c_recv) = unbounded();
+        let (d2_send, d2_recv) = unbounded();
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            d_command_recv,
+            d1_recv,
+            HashMap::from([(1, c_send), (12, d2_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .priority_scheduling(1)
+        .build();
+
+        // The first packet moves the others to the inbox, then the ACK is handled
+        for fragment_index in 1..3 {
+            d1_send.send(create_fragment(fragment_index)).unwrap();
+        }
+        d1_send.send(create_ack(0)).unwrap();
+        drone.receive_packet(create_fragment(0));
+        assert_eq!(
+            d2_recv
+                .try_recv()
+                .map(|packet| packet_type_name(&packet.pack_type)),
+            Ok("Ack")
+        );
+
+        drone.handle_command(DroneCommand::Crash);
+        d_command_send.send(DroneCommand::RemoveSender(1)).unwrap();
+        d_command_send.send(DroneCommand::RemoveSender(12)).unwrap();
+        drone.run();
+
+        let nacks: Vec<NackType> = c_recv
+            .try_iter()
+            .map(|packet| match packet.pack_type {
+                PacketType::Nack(nack) => nack.nack_type,
+                other => panic!("Expected a NACK, received {:?}", other),
+            })
+            .collect();
+        assert_eq!(nacks, [NackType::ErrorInRouting(11); 3]);
+        assert!(d2_recv.try_recv().is_err());
+    }
+}
@@ -0,0 +1,148 @@
+/// Run tests on the replay of packet captures
+#[cfg(test)]
+mod replay_tests {
+    use crate::capture::{read_capture, CaptureRecord, CaptureWriter, Direction};
+    use crate::replay::Replay;
+    use crate::tests::fixtures::{create_flood_request, create_fragment};
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::unbounded;
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+    use std::{env, fs};
+    use wg_2024::packet::PacketType;
+
+    /// Record the traffic of drone 11, with neighbors 1, 12 and 13, dropping fragments
+    fn record(pdr: f32, seed: u64) -> Vec<CaptureRecord> {
+        let dir = env::temp_dir().join(format!("rustafarian-replay-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut neighbors = HashMap::new();
+        let mut receivers = Vec::new();
+        for id in [1, 12, 13] {
+            let (send, recv) = unbounded();
+            neighbors.insert(id, send);
+            receivers.push(recv);
+        }
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            unbounded().1,
+            neighbors,
+        )
+        .log_level(LogLevel::NONE)
+        .pdr(pdr)
+        .seed(seed)
+        .capture(CaptureWriter::in_dir(&dir, 11).unwrap())
+        .build();
+        drone.handle_packet(create_flood_request(1));
+        for index in 0..40 {
+            drone.handle_packet(create_fragment(index, vec![1, 11, 12, 2]));
+        }
+        // 21 is not a neighbor, the fragment is NACKed
+        drone.handle_packet(create_fragment(40, vec![1, 11, 21, 2]));
+        drop(drone);
+
+        let records = read_capture(dir.join("capture-11.jsonl")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        records
+    }
+
+    /// Check that a capture replayed with the same settings gives the same packets, and that a
+    /// different seed drops different fragments
+    #[test]
+    fn run_replay_exact() {
+        let records = record(0.5, 7);
+        let replay = Replay::new(11, records);
+        assert_eq!(replay.len(), 42);
+        assert_eq!(
+            replay.neighbors().iter().copied().collect::<Vec<_>>(),
+            [1, 12, 13]
+        );
+
+        let report = replay.run_with(|builder| builder.log_level(LogLevel::NONE).pdr(0.5).seed(7));
+        assert!(report.is_exact(), "{:?}", report.divergences);
+        assert_eq!((report.injected, report.matched), (42, 42));
+
+        let report = replay.run_with(|builder| builder.log_level(LogLevel::NONE).pdr(0.5).seed(8));
+        assert!(!report.is_exact());
+        assert_eq!(report.injected, 42);
+        assert_eq!(report.matched + report.divergences.len(), 42);
+        // Every divergence is a fragment forwarded by one drone and NACKed by the other
+        for divergence in &report.divergences {
+            assert!(matches!(
+                divergence.ingress.as_ref().map(|packet| &packet.pack_type),
+                Some(PacketType::MsgFragment(_))
+            ));
+            assert_eq!(divergence.recorded.len(), 1);
+            assert_eq!(divergence.replayed.len(), 1);
+            assert_ne!(divergence.recorded[0].0, divergence.replayed[0].0);
+        }
+    }
+
+    /// Check that the divergences report what was recorded and what the drone sent, including
+    /// packets recorded before any packet was received
+    #[test]
+    fn run_replay_divergences() {
+        let fragment = create_fragment(0, vec![1, 11, 12, 2]);
+        let mut forwarded = fragment.clone();
+        forwarded.routing_header.hop_index = 2;
+        let orphan = create_fragment(1, vec![13, 11, 1]);
+
+        let records = vec![
+            CaptureRecord::new(11, Direction::Egress, Some(1), orphan.clone()),
+            CaptureRecord::new(11, Direction::Ingress, Some(1), fragment.clone()),
+            // Recorded towards the wrong neighbor
+            CaptureRecord::new(11, Direction::Egress, Some(13), forwarded.clone()),
+            // Records of other drones are ignored
+            CaptureRecord::new(12, Direction::Ingress, Some(11), forwarded.clone()),
+            CaptureRecord::new(11, Direction::Ingress, Some(1), fragment.clone()),
+            CaptureRecord::new(11, Direction::Egress, Some(12), forwarded.clone()),
+        ];
+
+        let report = Replay::new(11, records).run_with(|builder| builder.log_level(LogLevel::NONE));
+        assert_eq!((report.injected, report.matched), (2, 1));
+        assert_eq!(report.divergences.len(), 2);
+
+        let orphan_step = &report.divergences[0];
+        assert_eq!(orphan_step.step, 0);
+        assert_eq!(orphan_step.ingress, None);
+        assert_eq!(orphan_step.recorded, [(1, orphan)]);
+        assert!(orphan_step.replayed.is_empty());
+
+        let wrong_neighbor = &report.divergences[1];
+        assert_eq!(wrong_neighbor.step, 1);
+        assert_eq!(wrong_neighbor.ingress, Some(fragment));
+        assert_eq!(wrong_neighbor.recorded, [(13, forwarded.clone())]);
+        assert_eq!(wrong_neighbor.replayed, [(12, forwarded)]);
+    }
+
+    /// Check that the packets are injected with the recorded timing, scaled by the speed
+    #[test]
+    fn run_replay_timing() {
+        let mut records: Vec<CaptureRecord> = (0..3)
+            .map(|index| {
+                let fragment = create_fragment(index, vec![1, 11, 12, 2]);
+                CaptureRecord::new(11, Direction::Ingress, Some(1), fragment)
+            })
+            .collect();
+        let first = records[0].timestamp_us;
+        for (index, record) in records.iter_mut().enumerate() {
+            record.timestamp_us = first + index as u64 * 100_000;
+        }
+        let replay = Replay::new(11, records).neighbor(12);
+
+        let start = Instant::now();
+        let report = replay
+            .clone()
+            .original_timing(2.0)
+            .run_with(|builder| builder.log_level(LogLevel::NONE));
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        // Nothing was recorded as sent, every forwarded fragment diverges
+        assert_eq!(report.divergences.len(), 3);
+
+        let start = Instant::now();
+        replay.run_with(|builder| builder.log_level(LogLevel::NONE));
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,122 @@
+/// Run tests on the routing errors, for every type of packet
+#[cfg(test)]
+mod routing_errors_tests {
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::unbounded;
+    use std::collections::HashMap;
+    use wg_2024::controller::DroneEvent;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{
+        Ack, FloodResponse, Fragment, Nack, NackType, NodeType, Packet, PacketType,
+    };
+
+    fn packet_types() -> Vec<PacketType> {
+        vec![
+            PacketType::MsgFragment(Fragment {
+                fragment_index: 4,
+                total_n_fragments: 5,
+                length: 128,
+                data: [1; 128],
+            }),
+            PacketType::Ack(Ack { fragment_index: 4 }),
+            PacketType::Nack(Nack {
+                fragment_index: 4,
+                nack_type: NackType::Dropped,
+            }),
+            PacketType::FloodResponse(FloodResponse {
+                flood_id: 1,
+                path_trace: vec![(1, NodeType::Client), (11, NodeType::Drone)],
+            }),
+        ]
+    }
+
+    /// Send the packet to drone 11, connected to client 1 and to drone 12, and check the outcome:
+    /// fragments are answered with a NACK, the other packets are sent to the controller.
+    ///
+    /// ### Arguments
+    ///
+    /// * `hop_index: usize` - The hop index of the packet received by the drone
+    /// * `hops: Vec<u8>` - The route of the packet
+    /// * `nack_type: NackType` - The routing error expected
+    /// * `shortcut_hop_index: usize` - The hop index of the packet sent to the controller
+    fn check_routing_error(
+        hop_index: usize,
+        hops: Vec<u8>,
+        nack_type: NackType,
+        shortcut_hop_index: usize,
+    ) {
+        for pack_type in packet_types() {
+            let (c_send, c_recv) = unbounded();
+            let (d2_send, d2_recv) = unbounded();
+            let (controller_send, controller_recv) = unbounded();
+
+            let mut drone = RustafarianDroneBuilder::new(
+                11,
+                controller_send,
+                unbounded().1,
+                unbounded().1,
+                HashMap::from([(1, c_send), (12, d2_send)]),
+            )
+            .log_level(LogLevel::NONE)
+            .build();
+
+            let packet = Packet {
+                pack_type,
+                routing_header: SourceRoutingHeader {
+                    hop_index,
+                    hops: hops.clone(),
+                },
+                session_id: 1,
+            };
+            drone.handle_packet(packet.clone());
+
+            let shortcuts: Vec<Packet> = controller_recv
+                .try_iter()
+                .filter_map(|event| match event {
+                    DroneEvent::ControllerShortcut(packet) => Some(packet),
+                    _ => None,
+                })
+                .collect();
+            assert!(d2_recv.try_recv().is_err(), "Nothing should be forwarded");
+
+            match packet.pack_type {
+                PacketType::MsgFragment(_) => match c_recv.try_recv().map(|nack| nack.pack_type) {
+                    Ok(PacketType::Nack(nack)) => {
+                        assert_eq!(nack.nack_type, nack_type);
+                        assert_eq!(nack.fragment_index, 4);
+                    }
+                    other => panic!("Expected a NACK, received {:?}", other),
+                },
+                _ => {
+                    let mut expected = packet.clone();
+                    expected.routing_header.hop_index = shortcut_hop_index;
+                    assert_eq!(shortcuts, vec![expected]);
+                    assert!(
+                        c_recv.try_recv().is_err(),
+                        "No NACK should be created for {:?}",
+                        packet.pack_type
+                    );
+                    assert!(drone.stats().snapshot().nacks.is_empty());
+                }
+            }
+        }
+    }
+
+    /// The drone is not the current hop
+    #[test]
+    fn run_routing_error_unexpected_recipient() {
+        check_routing_error(1, vec![1, 13, 12], NackType::UnexpectedRecipient(11), 1);
+    }
+
+    /// The drone is the last hop
+    #[test]
+    fn run_routing_error_destination_is_drone() {
+        check_routing_error(1, vec![1, 11], NackType::DestinationIsDrone, 1);
+    }
+
+    /// The next hop is not a neighbor of the drone
+    #[test]
+    fn run_routing_error_not_a_neighbor() {
+        check_routing_error(1, vec![1, 11, 21], NackType::ErrorInRouting(21), 2);
+    }
+}
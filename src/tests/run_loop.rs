@@ -0,0 +1,111 @@
+/// Run tests on the run loop when the channels of the drone are closed
+#[cfg(test)]
+mod run_loop_tests {
+    use crate::stats::{StatsHandle, StopReason};
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender, TryRecvError};
+    use std::collections::HashMap;
+    use std::thread;
+    use std::time::Duration;
+    use wg_2024::controller::DroneCommand;
+    use wg_2024::drone::Drone;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, Packet, PacketType};
+
+    fn create_ack() -> Packet {
+        Packet {
+            pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 12],
+            },
+            session_id: 1,
+        }
+    }
+
+    /// Start drone 11, connected to drone 12. Returns the command and packet channels of the
+    /// drone, the channel of drone 12, the stats and a channel closed when the drone stops.
+    fn start_drone() -> (
+        Sender<DroneCommand>,
+        Sender<Packet>,
+        Receiver<Packet>,
+        StatsHandle,
+        Receiver<()>,
+    ) {
+        let (d_command_send, d_command_recv) = unbounded();
+        let (d1_send, d1_recv) = unbounded();
+        let (d2_send, d2_recv) = unbounded();
+        let (stopped_send, stopped_recv) = unbounded::<()>();
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            d_command_recv,
+            d1_recv,
+            HashMap::from([(12, d2_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .build();
+        let stats = drone.stats();
+
+        thread::spawn(move || {
+            let _stopped_send = stopped_send;
+            drone.run();
+        });
+
+        (d_command_send, d1_send, d2_recv, stats, stopped_recv)
+    }
+
+    /// Check that the drone keeps forwarding packets after the controller channel is closed
+    #[test]
+    fn run_loop_controller_closed() {
+        let (d_command_send, d1_send, d2_recv, stats, stopped_recv) = start_drone();
+
+        drop(d_command_send);
+        thread::sleep(Duration::from_millis(10));
+        d1_send.send(create_ack()).unwrap();
+
+        assert!(d2_recv.recv_timeout(Duration::from_secs(1)).is_ok());
+        assert_eq!(
+            stopped_recv.try_recv(),
+            Err(TryRecvError::Empty),
+            "The drone should be running"
+        );
+        assert_eq!(stats.snapshot().stopped, None);
+    }
+
+    /// Check that the drone stops once both input channels are closed
+    #[test]
+    fn run_loop_disconnected() {
+        let (d_command_send, d1_send, _d2_recv, stats, stopped_recv) = start_drone();
+
+        drop(d1_send);
+        d_command_send
+            .send(DroneCommand::SetPacketDropRate(0.5))
+            .unwrap();
+        drop(d_command_send);
+
+        assert_eq!(
+            stopped_recv.recv_timeout(Duration::from_secs(1)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+        assert_eq!(stats.snapshot().stopped, Some(StopReason::Disconnected));
+    }
+
+    /// Check that a crashed drone stops once its packet channel is closed
+    #[test]
+    fn run_loop_crashed() {
+        let (d_command_send, d1_send, d2_recv, stats, stopped_recv) = start_drone();
+
+        d_command_send.send(DroneCommand::Crash).unwrap();
+        d1_send.send(create_ack()).unwrap();
+        drop(d1_send);
+
+        assert!(d2_recv.recv_timeout(Duration::from_secs(1)).is_ok());
+        assert_eq!(
+            stopped_recv.recv_timeout(Duration::from_secs(1)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+        assert_eq!(stats.snapshot().stopped, Some(StopReason::Crashed));
+    }
+}
@@ -0,0 +1,89 @@
+/// Run tests on the randomness used to drop packets
+#[cfg(test)]
+mod seed_tests {
+    use crate::{drone_seed, LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::{select, unbounded};
+    use rand::rngs::mock::StepRng;
+    use std::collections::HashMap;
+    use std::thread;
+    use wg_2024::drone::Drone;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, Packet, PacketType};
+
+    /// Send `count` fragments through drone 11 and return the indexes of the dropped ones
+    fn dropped_fragments(
+        count: u64,
+        configure: impl Fn(RustafarianDroneBuilder) -> RustafarianDroneBuilder,
+    ) -> Vec<u64> {
+        let (c_send, c_recv) = unbounded();
+        let (s_send, s_recv) = unbounded();
+        let (d1_send, d1_recv) = unbounded();
+        let (_d_command_send, d_command_recv) = unbounded();
+
+        let neighbours = HashMap::from([(1, c_send), (21, s_send)]);
+
+        let builder =
+            RustafarianDroneBuilder::new(11, unbounded().0, d_command_recv, d1_recv, neighbours)
+                .log_level(LogLevel::NONE);
+        let mut drone = configure(builder).build();
+
+        thread::spawn(move || {
+            drone.run();
+        });
+
+        let mut dropped = vec![];
+        for fragment_index in 0..count {
+            d1_send
+                .send(Packet {
+                    pack_type: PacketType::MsgFragment(Fragment {
+                        fragment_index,
+                        total_n_fragments: count,
+                        length: 128,
+                        data: [2; 128],
+                    }),
+                    routing_header: SourceRoutingHeader {
+                        hop_index: 1,
+                        hops: vec![1, 11, 21],
+                    },
+                    session_id: 1,
+                })
+                .unwrap();
+
+            select! {
+                recv(c_recv) -> _nack => dropped.push(fragment_index),
+                recv(s_recv) -> _fragment => {},
+            }
+        }
+
+        dropped
+    }
+
+    /// Check that the same global seed drops exactly the same fragments
+    #[test]
+    fn run_global_seed_reproducible() {
+        let first = dropped_fragments(100, |builder| builder.pdr(0.3).global_seed(7));
+        let second = dropped_fragments(100, |builder| builder.pdr(0.3).global_seed(7));
+
+        assert!(!first.is_empty(), "Some fragments should be dropped");
+        assert_eq!(first, second);
+    }
+
+    /// Check that the seed of a drone depends both on the global seed and on its ID
+    #[test]
+    fn run_drone_seed() {
+        assert_eq!(drone_seed(7, 1), drone_seed(7, 1));
+        assert_ne!(drone_seed(7, 1), drone_seed(7, 2));
+        assert_ne!(drone_seed(7, 1), drone_seed(8, 1));
+    }
+
+    /// Check that an injected RNG is used instead of the seeded one
+    #[test]
+    fn run_injected_rng() {
+        // Always generates the highest value, so no fragment is below the PDR
+        let dropped = dropped_fragments(20, |builder| {
+            builder.pdr(0.99).seed(1).rng(StepRng::new(u64::MAX, 0))
+        });
+
+        assert!(dropped.is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+/// Run tests on the discrete-event simulator
+#[cfg(test)]
+mod simulator_tests {
+    use crate::harness::{Topology, CLIENT, SERVER};
+    use crate::simulator::{SimEvent, Simulator, DEFAULT_LATENCY};
+    use crate::tests::fixtures::{create_flood_request, create_fragment};
+    use crate::LogLevel;
+    use std::time::Duration;
+    use wg_2024::controller::DroneEvent;
+    use wg_2024::network::NodeId;
+    use wg_2024::packet::{NackType, PacketType};
+
+    fn simulator(topology: Topology, seed: u64) -> Simulator {
+        Simulator::from_config_with(topology.config(), seed, |builder| {
+            builder.log_level(LogLevel::NONE)
+        })
+    }
+
+    /// Check that a fragment reaches the server after the latency of every link, and that the
+    /// clock only moves with the inputs
+    #[test]
+    fn run_simulator_latency() {
+        // Client 1 - 11 - 12 - 13 - Server 2
+        let mut simulator = simulator(Topology::line(3), 1);
+        simulator.set_latency(12, 11, Duration::from_millis(10));
+
+        simulator.inject(create_fragment(0, vec![CLIENT, 11, 12, 13, SERVER]));
+        simulator.run();
+
+        let arrival = Duration::from_millis(10) + 3 * DEFAULT_LATENCY;
+        assert_eq!(simulator.now(), arrival);
+        assert_eq!(simulator.delivered(SERVER).len(), 1);
+        let sent: Vec<(Duration, NodeId)> = simulator
+            .timeline()
+            .iter()
+            .filter_map(|(time, event)| match event {
+                SimEvent::Drone(drone, DroneEvent::PacketSent(_)) => Some((*time, *drone)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            sent,
+            [
+                (DEFAULT_LATENCY, 11),
+                (DEFAULT_LATENCY + Duration::from_millis(10), 12),
+                (DEFAULT_LATENCY * 2 + Duration::from_millis(10), 13),
+            ]
+        );
+
+        simulator.run_for(Duration::from_secs(1));
+        assert_eq!(simulator.now(), arrival + Duration::from_secs(1));
+    }
+
+    /// Check that the same seed gives the same timeline, with drops and floods
+    #[test]
+    fn run_simulator_deterministic() {
+        let run = |seed| {
+            let mut simulator = simulator(Topology::mesh(3, 3).pdr(0.3), seed);
+            simulator.send(CLIENT, 11, create_flood_request(1));
+            for index in 0..50 {
+                let fragment = create_fragment(index, vec![CLIENT, 11, 12, 13, 16, 19, SERVER]);
+                simulator.inject(fragment);
+            }
+            simulator.run();
+            simulator.take_timeline()
+        };
+
+        let timeline = run(7);
+        assert!(timeline
+            .iter()
+            .any(|(_, event)| matches!(event, SimEvent::Drone(_, DroneEvent::PacketDropped(_)))));
+        assert_eq!(timeline, run(7));
+        assert_ne!(timeline, run(8));
+    }
+
+    /// Check that a crashed drone is removed from its neighbors, and that a week of traffic is
+    /// simulated without waiting
+    #[test]
+    fn run_simulator_crash_and_long_run() {
+        let mut simulator = simulator(Topology::line(3), 1);
+        let hour = Duration::from_secs(60 * 60);
+        for index in 0..24 * 7 {
+            let fragment = create_fragment(index, vec![CLIENT, 11, 12, 13, SERVER]);
+            simulator.send_at(hour * index as u32, CLIENT, 11, fragment);
+        }
+
+        // Crash drone 12 after three days
+        simulator.run_until(hour * 24 * 3);
+        assert_eq!(simulator.delivered(SERVER).len(), 24 * 3);
+        simulator.crash(12);
+        simulator.run();
+
+        assert!(simulator.now() >= hour * (24 * 7 - 1));
+        assert_eq!(simulator.delivered(SERVER).len(), 24 * 3);
+        let nacks = simulator
+            .delivered(CLIENT)
+            .into_iter()
+            .filter(|packet| match &packet.pack_type {
+                PacketType::Nack(nack) => nack.nack_type == NackType::ErrorInRouting(12),
+                _ => false,
+            })
+            .count();
+        assert_eq!(nacks, 24 * 4);
+        assert_eq!(simulator.stats(11).nacks["ErrorInRouting"], 24 * 4);
+    }
+}
@@ -0,0 +1,123 @@
+/// Run tests on the drone statistics
+#[cfg(test)]
+mod stats_tests {
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::unbounded;
+    use std::collections::HashMap;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, FloodRequest, Fragment, NodeType, Packet, PacketType};
+
+    fn create_fragment(fragment_index: u64, hops: Vec<u8>) -> Packet {
+        Packet {
+            pack_type: PacketType::MsgFragment(Fragment {
+                fragment_index,
+                total_n_fragments: 1,
+                length: 128,
+                data: [2; 128],
+            }),
+            routing_header: SourceRoutingHeader { hop_index: 1, hops },
+            session_id: 1,
+        }
+    }
+
+    /// Check that the observed drop rate matches the configured PDR
+    #[test]
+    fn run_stats_drop_rate() {
+        let (c_send, _c_recv) = unbounded();
+        let (s_send, _s_recv) = unbounded();
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send), (21, s_send)]),
+        )
+        .pdr(0.3)
+        .seed(3)
+        .log_level(LogLevel::NONE)
+        .build();
+        let stats = drone.stats();
+
+        for fragment_index in 0..2000 {
+            drone.handle_packet(create_fragment(fragment_index, vec![1, 11, 21]));
+        }
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.received["MsgFragment"], 2000);
+        assert_eq!(snapshot.drop_checks, 2000);
+        assert_eq!(snapshot.dropped, snapshot.nacks["Dropped"]);
+        assert_eq!(snapshot.dropped_per_neighbor[&21], snapshot.dropped);
+        assert_eq!(snapshot.sent["MsgFragment"] + snapshot.dropped, 2000);
+        assert!(
+            (snapshot.drop_rate() - 0.3).abs() < 0.05,
+            "Observed drop rate {} is too far from the PDR",
+            snapshot.drop_rate()
+        );
+    }
+
+    /// Check the counters per packet type, per neighbor and per NACK type
+    #[test]
+    fn run_stats_counters() {
+        let (c_send, _c_recv) = unbounded();
+        let (d2_send, _d2_recv) = unbounded();
+        let (d3_send, d3_recv) = unbounded::<Packet>();
+        // Drone 13 has crashed, its channel is closed
+        drop(d3_recv);
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send), (12, d2_send), (13, d3_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .build();
+        let stats = drone.stats();
+
+        drone.handle_packet(create_fragment(0, vec![1, 11, 12]));
+        // 11 is the destination
+        drone.handle_packet(create_fragment(1, vec![1, 11]));
+        // The ACK can't reach 13, so it goes through the Simulation Controller
+        drone.handle_packet(Packet {
+            pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 13],
+            },
+            session_id: 1,
+        });
+        let flood_request = Packet {
+            pack_type: PacketType::FloodRequest(FloodRequest {
+                flood_id: 1,
+                initiator_id: 1,
+                path_trace: vec![(1, NodeType::Client)],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 0,
+                hops: vec![],
+            },
+            session_id: 1,
+        };
+        drone.handle_packet(flood_request.clone());
+        // Already seen, so the drone answers with a response
+        drone.handle_packet(flood_request);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.total_received(), 5);
+        assert_eq!(snapshot.received["MsgFragment"], 2);
+        assert_eq!(snapshot.received["Ack"], 1);
+        assert_eq!(snapshot.received["FloodRequest"], 2);
+        assert_eq!(snapshot.sent["MsgFragment"], 1);
+        assert_eq!(snapshot.sent["FloodResponse"], 1);
+        assert_eq!(snapshot.nacks["DestinationIsDrone"], 1);
+        assert_eq!(snapshot.flood_responses, 1);
+        assert!(snapshot.shortcuts >= 1);
+        assert_eq!(snapshot.sent_per_neighbor[&12], 2);
+        assert!(snapshot.sent_per_neighbor[&1] >= 2);
+
+        stats.reset();
+        assert_eq!(stats.snapshot().total_received(), 0);
+    }
+}
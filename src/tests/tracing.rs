@@ -0,0 +1,107 @@
+/// Run tests on the `tracing` integration
+#[cfg(all(test, feature = "tracing"))]
+mod tracing_tests {
+    use crate::RustafarianDroneBuilder;
+    use crossbeam_channel::unbounded;
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, Packet, PacketType};
+
+    type Fields = HashMap<String, String>;
+
+    /// Visitor saving every field of an event as a string
+    struct FieldVisitor(Fields);
+
+    /// Subscriber saving the fields of every event
+    struct CaptureSubscriber {
+        events: Arc<Mutex<Vec<(Level, Fields)>>>,
+    }
+
+    impl Visit for FieldVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    impl Subscriber for CaptureSubscriber {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut visitor = FieldVisitor(Fields::new());
+            event.record(&mut visitor);
+            let level = *event.metadata().level();
+            self.events.lock().unwrap().push((level, visitor.0));
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    /// Check that an error about a packet is sent to tracing with the structured fields
+    #[test]
+    fn run_tracing_fields() {
+        let (c_send, _c_recv) = unbounded();
+        let (_d1_send, d1_recv) = unbounded();
+        let (_d_command_send, d_command_recv) = unbounded();
+
+        // Node 21 is not a neighbor, so the drone logs an error
+        let neighbours = HashMap::from([(1, c_send)]);
+        let mut drone =
+            RustafarianDroneBuilder::new(11, unbounded().0, d_command_recv, d1_recv, neighbours)
+                .build();
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let subscriber = CaptureSubscriber {
+            events: events.clone(),
+        };
+
+        tracing::subscriber::with_default(subscriber, || {
+            drone.handle_packet(Packet {
+                pack_type: PacketType::MsgFragment(Fragment {
+                    fragment_index: 0,
+                    total_n_fragments: 1,
+                    length: 128,
+                    data: [2; 128],
+                }),
+                routing_header: SourceRoutingHeader {
+                    hop_index: 1,
+                    hops: vec![1, 11, 21],
+                },
+                session_id: 7,
+            });
+        });
+
+        let events = events.lock().unwrap();
+        let (level, fields) = events
+            .iter()
+            .find(|(_, fields)| fields.contains_key("packet_type"))
+            .expect("The error should be sent to tracing");
+
+        assert_eq!(*level, Level::ERROR);
+        assert_eq!(fields["drone_id"], "11");
+        assert_eq!(fields["packet_type"], "MsgFragment");
+        assert_eq!(fields["session_id"], "7");
+        assert!(fields["message"].contains("is not a neighbor"));
+    }
+}
@@ -0,0 +1,173 @@
+/// Run tests on the validation of malformed packets
+#[cfg(test)]
+mod validation_tests {
+    use crate::validation::{validate_packet, PacketError};
+    use crate::{LogLevel, RustafarianDroneBuilder};
+    use crossbeam_channel::unbounded;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
+    use wg_2024::controller::{DroneCommand, DroneEvent};
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{
+        Ack, FloodRequest, FloodResponse, Fragment, Nack, NackType, NodeType, Packet, PacketType,
+    };
+
+    fn create_packet(pack_type: PacketType, hop_index: usize, hops: Vec<u8>) -> Packet {
+        Packet {
+            pack_type,
+            routing_header: SourceRoutingHeader { hop_index, hops },
+            session_id: 1,
+        }
+    }
+
+    fn create_fragment() -> PacketType {
+        PacketType::MsgFragment(Fragment {
+            fragment_index: 3,
+            total_n_fragments: 4,
+            length: 128,
+            data: [2; 128],
+        })
+    }
+
+    /// Check the problem found for each kind of malformed packet
+    #[test]
+    fn run_validation_errors() {
+        let ack = PacketType::Ack(Ack { fragment_index: 0 });
+        let empty_flood = PacketType::FloodRequest(FloodRequest {
+            flood_id: 1,
+            initiator_id: 1,
+            path_trace: vec![],
+        });
+
+        assert_eq!(
+            validate_packet(&create_packet(ack.clone(), 1, vec![])),
+            Err(PacketError::EmptyHops)
+        );
+        assert_eq!(
+            validate_packet(&create_packet(ack.clone(), 0, vec![1, 11, 21])),
+            Err(PacketError::ZeroHopIndex)
+        );
+        assert_eq!(
+            validate_packet(&create_packet(ack.clone(), 3, vec![1, 11, 21])),
+            Err(PacketError::HopIndexOutOfRange {
+                hop_index: 3,
+                hops: 3
+            })
+        );
+        assert_eq!(
+            validate_packet(&create_packet(empty_flood, 0, vec![])),
+            Err(PacketError::EmptyPathTrace)
+        );
+        assert_eq!(
+            validate_packet(&create_packet(ack, 1, vec![1, 11, 21])),
+            Ok(())
+        );
+    }
+
+    /// Check that a malformed fragment is answered with a NACK, and that a malformed ACK is sent
+    /// to the Simulation Controller
+    #[test]
+    fn run_validation_rejected_packets() {
+        let (c_send, c_recv) = unbounded();
+        let (controller_send, controller_recv) = unbounded();
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            controller_send,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send)]),
+        )
+        .log_level(LogLevel::NONE)
+        .build();
+        let stats = drone.stats();
+
+        drone.handle_packet(create_packet(create_fragment(), 5, vec![1, 11, 21]));
+        match c_recv.try_recv().unwrap().pack_type {
+            PacketType::Nack(nack) => {
+                assert_eq!(nack.nack_type, NackType::UnexpectedRecipient(11));
+                assert_eq!(nack.fragment_index, 3);
+            }
+            other => panic!("Expected a NACK, received {:?}", other),
+        }
+
+        let ack = create_packet(
+            PacketType::Ack(Ack { fragment_index: 0 }),
+            0,
+            vec![21, 11, 1],
+        );
+        drone.handle_packet(ack.clone());
+        assert!(controller_recv
+            .try_iter()
+            .any(|event| event == DroneEvent::ControllerShortcut(ack.clone())));
+
+        // Without hops there is no one to answer
+        drone.handle_packet(create_packet(create_fragment(), 1, vec![]));
+        assert!(c_recv.try_recv().is_err());
+
+        assert_eq!(stats.snapshot().rejected, 3);
+    }
+
+    /// Send random packets, commands included, to a drone: none of them can make it panic
+    #[test]
+    fn run_validation_fuzz() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let (c_send, _c_recv) = unbounded();
+        let (d2_send, d2_recv) = unbounded();
+        drop(d2_recv);
+
+        let mut drone = RustafarianDroneBuilder::new(
+            11,
+            unbounded().0,
+            unbounded().1,
+            unbounded().1,
+            HashMap::from([(1, c_send), (12, d2_send)]),
+        )
+        .pdr(0.5)
+        .seed(11)
+        .log_level(LogLevel::NONE)
+        .build();
+
+        // Few IDs, so that the drone and its neighbors often appear in the packets
+        let ids = [0, 1, 11, 12, 13, 21, 255];
+        let random_ids = |rng: &mut StdRng, max_len: usize| -> Vec<u8> {
+            let len = rng.gen_range(0..=max_len);
+            (0..len).map(|_| ids[rng.gen_range(0..ids.len())]).collect()
+        };
+
+        for _ in 0..20_000 {
+            let path_trace = random_ids(&mut rng, 4)
+                .into_iter()
+                .map(|id| (id, NodeType::Drone))
+                .collect::<Vec<_>>();
+            let pack_type = match rng.gen_range(0..6) {
+                0 => create_fragment(),
+                1 => PacketType::Ack(Ack { fragment_index: 0 }),
+                2 => PacketType::Nack(Nack {
+                    fragment_index: 0,
+                    nack_type: NackType::Dropped,
+                }),
+                3 => PacketType::FloodRequest(FloodRequest {
+                    flood_id: rng.gen_range(0..4),
+                    initiator_id: 1,
+                    path_trace,
+                }),
+                _ => PacketType::FloodResponse(FloodResponse {
+                    flood_id: 0,
+                    path_trace,
+                }),
+            };
+            let hops = random_ids(&mut rng, 5);
+            let hop_index = rng.gen_range(0..7);
+            drone.handle_packet(create_packet(pack_type, hop_index, hops));
+
+            match rng.gen_range(0..200) {
+                0 => drone.handle_command(DroneCommand::Crash),
+                1 => drone.handle_command(DroneCommand::RemoveSender(12)),
+                2 => drone.handle_command(DroneCommand::SetPacketDropRate(rng.gen())),
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+use wg_2024::packet::{Packet, PacketType};
+
+/// Reason why a packet received by the drone is malformed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketError {
+    /// A flood request without any node in the path trace, so its sender is unknown
+    EmptyPathTrace,
+    /// A source routed packet without any hop
+    EmptyHops,
+    /// A source routed packet with `hop_index` 0: the first hop is the sender, never a drone
+    ZeroHopIndex,
+    /// A source routed packet with `hop_index` outside of the hops
+    HopIndexOutOfRange { hop_index: usize, hops: usize },
+}
+
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacketError::EmptyPathTrace => write!(f, "flood request with an empty path trace"),
+            PacketError::EmptyHops => write!(f, "routing header without hops"),
+            PacketError::ZeroHopIndex => write!(f, "routing header with hop_index 0"),
+            PacketError::HopIndexOutOfRange { hop_index, hops } => {
+                write!(f, "hop_index {} out of range for {} hops", hop_index, hops)
+            }
+        }
+    }
+}
+
+/// Check that a packet can be handled by the drone without panicking.
+///
+/// Flood requests are not source routed, so only their path trace is checked. Every other packet
+/// must have a routing header pointing to an existing hop, which can't be the first one.
+///
+/// ### Arguments
+///
+/// * `packet: &Packet` - The packet received by the drone
+/// ---
+/// ### Returns
+/// `Ok(())` if the packet is well formed, the first problem found otherwise
+pub fn validate_packet(packet: &Packet) -> Result<(), PacketError> {
+    if let PacketType::FloodRequest(request) = &packet.pack_type {
+        if request.path_trace.is_empty() {
+            return Err(PacketError::EmptyPathTrace);
+        }
+        return Ok(());
+    }
+
+    let header = &packet.routing_header;
+    if header.hops.is_empty() {
+        Err(PacketError::EmptyHops)
+    } else if header.hop_index == 0 {
+        Err(PacketError::ZeroHopIndex)
+    } else if header.hop_index >= header.hops.len() {
+        Err(PacketError::HopIndexOutOfRange {
+            hop_index: header.hop_index,
+            hops: header.hops.len(),
+        })
+    } else {
+        Ok(())
+    }
+}